@@ -0,0 +1,136 @@
+//! Frame time with thousands of lasers on screen, spawning and despawning them against recycling them through the pool.
+//! `Laser` is sparse in both runs like in the game, so only the pool makes the difference.
+//! Run with `cargo bench --bench laser_pool`.
+
+#[allow(dead_code)]
+#[path = "../src/pool.rs"]
+mod pool;
+
+use std::time::{Duration, Instant};
+
+use bevy::{ecs::schedule::{Schedule, Stage, SystemStage}, math::Vec3, prelude::{Bundle, Commands, Entity, IntoSystem, Query, ResMut, SpriteSheetBundle, Transform, With, World}};
+use pool::{Pool, pool_recycle, register_sparse};
+
+const HEIGHT: f32 = 800.;
+/** Units per frame, about 2.5% of the lasers leave the screen every frame */
+const STEP: f32 = 20.;
+const WARMUP_FRAMES: u32 = 60;
+const FRAMES: u32 = 600;
+const COUNTS: [usize; 4] = [1000, 2500, 5000, 10000];
+
+struct Laser;
+struct Speed(f32);
+
+type Parts = (Laser,);
+
+#[derive(Bundle)]
+struct LaserBundle {
+    laser: Laser,
+    speed: Speed,
+
+    #[bundle]
+    sprite: SpriteSheetBundle,
+}
+
+fn laser(y: f32) -> LaserBundle {
+    LaserBundle {
+        laser: Laser,
+        speed: Speed(STEP),
+        sprite: SpriteSheetBundle {
+            transform: Transform::from_translation(Vec3::new(0., y, 0.)),
+            ..Default::default()
+        },
+    }
+}
+
+fn laser_movement (
+    query: Query<(&mut Transform, &Speed), With<Laser>>,
+) {
+    query.for_each_mut(|(mut transform, speed)| {
+        transform.translation.y += speed.0;
+    });
+}
+
+fn churn_despawn (
+    mut commands: Commands,
+    query: Query<(Entity, &Transform), With<Laser>>,
+) {
+    query.for_each(|(entity, transform)| {
+        if transform.translation.y > HEIGHT {
+            commands.entity(entity).despawn();
+            commands.spawn_bundle(laser(0.));
+        }
+    });
+}
+
+fn churn_pool (
+    mut commands: Commands,
+    mut pool: ResMut<Pool<Parts>>,
+    query: Query<(Entity, &Transform), With<Laser>>,
+) {
+    query.for_each(|(entity, transform)| {
+        if transform.translation.y > HEIGHT {
+            pool.release(&mut commands, entity);
+            pool.spawn(&mut commands, laser(0.));
+        }
+    });
+}
+
+/** Mean time of a frame with `count` lasers */
+fn run(count: usize, pooled: bool) -> Duration {
+    let mut world = World::new();
+    register_sparse::<Laser>(&mut world);
+    let mut pool = Pool::<Parts>::default();
+    {
+        let mut queue = Default::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        for i in 0..count {
+            let y = i as f32 * HEIGHT / count as f32;
+            if pooled {
+                pool.spawn(&mut commands, laser(y));
+            } else {
+                commands.spawn_bundle(laser(y));
+            }
+        }
+        if pooled {
+            // Spare lasers for the ones released during a frame
+            pool.reserve(&mut commands, count / 20, || laser(0.));
+        }
+        queue.apply(&mut world);
+    }
+    world.insert_resource(pool);
+
+    let mut update = SystemStage::single_threaded().with_system(laser_movement.system());
+    update = if pooled {
+        update.with_system(churn_pool.system())
+    } else {
+        update.with_system(churn_despawn.system())
+    };
+    let mut schedule = Schedule::default();
+    schedule.add_stage("update", update);
+    schedule.add_stage("recycle", SystemStage::single_threaded().with_system(pool_recycle::<Parts>.system()));
+
+    for _ in 0..WARMUP_FRAMES {
+        schedule.run(&mut world);
+    }
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        schedule.run(&mut world);
+    }
+    start.elapsed() / FRAMES
+}
+
+fn main() {
+    println!("{:>8} {:>14} {:>14} {:>8}", "lasers", "spawn (µs)", "pool (µs)", "gain");
+    for count in COUNTS.iter() {
+        let spawned = run(*count, false);
+        let pooled = run(*count, true);
+        println!(
+            "{:>8} {:>14.1} {:>14.1} {:>7.1}%",
+            count,
+            spawned.as_secs_f64() * 1e6,
+            pooled.as_secs_f64() * 1e6,
+            (1. - pooled.as_secs_f64() / spawned.as_secs_f64()) * 100.,
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use bevy::{math::Vec3, prelude::{Commands, EventReader, EventWriter, HorizontalAlign, IntoSystem, Plugin, Query, Res, ResMut, Transform, VerticalAlign, With}, text::{Text, Text2dBundle, TextAlignment, TextStyle}};
+
+use crate::{game_systems::{GameMode, GameState, GameTime, RunStarted, Screen, StageStatus}, stage_systems::{LoadStage, STAGES, ending_spawn}, util::{Materials, Rng, WinSize}};
+
+/** Time spent in the run, for the boss rush and score attack */
+#[derive(Default)]
+pub struct RunTimer(pub f32);
+impl RunTimer {
+    pub fn text(&self) -> String {
+        clock(self.0)
+    }
+
+    /** Tenths of a second, how clear times are kept with the high scores */
+    pub fn tenths(&self) -> u32 {
+        (self.0 * 10.).round() as u32
+    }
+}
+
+pub struct TimerText;
+
+/** `m:ss.s` */
+pub fn clock(seconds: f32) -> String {
+    format!("{}:{:04.1}", (seconds / 60.) as u32, seconds % 60.)
+}
+
+fn timer_spawn (
+    mut commands: Commands,
+    assets: Res<Materials>,
+    win_size: Res<WinSize>,
+) {
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 20.,
+                    ..Default::default()
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Bottom,
+                    horizontal: HorizontalAlign::Right,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(15., win_size.h - 50., 69.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(TimerText);
+}
+
+/** Boss rush goes straight to the first boss */
+fn attack_start (
+    mut started: EventReader<RunStarted>,
+    mode: Res<GameMode>,
+    mut run_timer: ResMut<RunTimer>,
+    mut load: EventWriter<LoadStage>,
+) {
+    if started.iter().next().is_none() {
+        return;
+    }
+    run_timer.0 = 0.;
+    if *mode == GameMode::BossRush {
+        load.send(LoadStage { index: 0, distance: STAGES[0].distance });
+    }
+}
+
+/** Ends score attack runs once their time is up */
+fn timer_update (
+    mut commands: Commands,
+    time: Res<GameTime>,
+    assets: Res<Materials>,
+    win_size: Res<WinSize>,
+    mode: Res<GameMode>,
+    rng: Res<Rng>,
+    mut run_timer: ResMut<RunTimer>,
+    mut screen: ResMut<Screen>,
+    mut game_state: ResMut<GameState>,
+) {
+    if matches!(game_state.status, StageStatus::GameOver | StageStatus::TimeUp) {
+        return;
+    }
+    run_timer.0 += time.delta().as_secs_f32();
+    let limit = match mode.time_limit() {
+        Some(limit) if run_timer.0 >= limit => limit,
+        _ => return,
+    };
+    game_state.status = StageStatus::TimeUp;
+    *screen = Screen::Ending;
+    let subtitle = format!("{} minutes of cooking", (limit / 60.).round());
+    ending_spawn(&mut commands, &assets, &win_size, "TIME UP", &subtitle, game_state.score, &rng);
+}
+
+fn timer_update_ui (
+    mode: Res<GameMode>,
+    run_timer: Res<RunTimer>,
+    query: Query<&mut Text, With<TimerText>>,
+) {
+    let value = match mode.time_limit() {
+        Some(limit) => clock((limit - run_timer.0).max(0.)),
+        None if *mode == GameMode::BossRush => run_timer.text(),
+        None => String::new(),
+    };
+    query.for_each_mut(|mut text| {
+        text.sections.get_mut(0).unwrap().value = value.clone();
+    });
+}
+
+pub struct AttackPlugin;
+impl Plugin for AttackPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .insert_resource(RunTimer::default())
+            .add_startup_system_to_stage("game_setup_ui", timer_spawn.system())
+            .add_system(attack_start.system())
+            .add_system(timer_update.system())
+            .add_system(timer_update_ui.system());
+    }
+}
@@ -0,0 +1,269 @@
+use bevy::{input::Input, math::{Quat, Vec2, Vec3}, prelude::{Assets, Commands, DespawnRecursiveExt, Entity, GlobalTransform, Handle, IntoSystem, KeyCode, Plugin, Query, Res, ResMut, SpriteBundle, Transform, With, Without}, render::color::Color, sprite::{ColorMaterial, Sprite}};
+
+use crate::{enemy_systems::{AI, AIBehavior, AIState}, game_systems::GameTime, health_systems::Invulnerable, part_systems::Shielded, player_systems::{Player, PlayerState}, util::{Health, HitBox}};
+
+//#region Components
+#[derive(Clone, Copy, PartialEq)]
+pub enum BeamState {
+    Off,
+    /** Visible warning line, doesn't hurt */
+    Telegraph,
+    Firing,
+}
+
+/** Continuous beam fired from the entity, stops at the first thing it hits */
+pub struct BeamEmitter {
+    pub offset: Vec3,
+    pub direction: Vec2,
+    pub width: f32,
+    pub dps: f32,
+    pub max_length: f32,
+    pub state: BeamState,
+    /** Current length, up to the first hit */
+    pub length: f32,
+    pub sprite: Option<Entity>,
+}
+impl Default for BeamEmitter {
+    fn default() -> Self {
+        Self {
+            offset: Vec3::ZERO,
+            direction: Vec2::new(0., 1.),
+            width: 12.,
+            dps: 12.,
+            max_length: 1000.,
+            state: BeamState::Off,
+            length: 0.,
+            sprite: None,
+        }
+    }
+}
+
+/** Loops an enemy beam through telegraph, firing and cooldown, in seconds */
+pub struct BeamCycle {
+    pub telegraph: f32,
+    pub firing: f32,
+    pub cooldown: f32,
+    pub step: f32,
+}
+impl Default for BeamCycle {
+    fn default() -> Self {
+        Self {
+            telegraph: 1.,
+            firing: 1.5,
+            cooldown: 3.,
+            step: 0.,
+        }
+    }
+}
+
+/** Stops beams of both sides, like the hull of armored cores */
+pub struct BeamBlocker;
+
+pub struct BeamSprite {
+    pub owner: Entity,
+}
+//#endregion
+
+pub struct BeamMaterials {
+    pub player: Handle<ColorMaterial>,
+    pub enemy: Handle<ColorMaterial>,
+    pub telegraph: Handle<ColorMaterial>,
+}
+
+fn beam_setup (
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(BeamMaterials {
+        player: materials.add(Color::rgba(0.5, 0.9, 1., 0.9).into()),
+        enemy: materials.add(Color::rgba(1., 0.3, 0.2, 0.9).into()),
+        telegraph: materials.add(Color::rgba(1., 0.3, 0.2, 0.3).into()),
+    });
+}
+
+/** Walks the beam until the closest hitbox, returns its length */
+fn beam_raycast<'a> (
+    emitter: &BeamEmitter,
+    from: Vec2,
+    hitboxes: impl Iterator<Item = (&'a GlobalTransform, &'a HitBox)>,
+) -> f32 {
+    hitboxes
+        .filter_map(|(transform, hitbox)| {
+            hitbox.raycast(&transform.translation, from, emitter.direction, emitter.width / 2.)
+        })
+        .fold(emitter.max_length, f32::min)
+}
+
+fn player_beam_input (
+    keyboard_input: Res<Input<KeyCode>>,
+    query: Query<(&mut BeamEmitter, &PlayerState), With<Player>>,
+) {
+    query.for_each_mut(|(mut beam, state)| {
+        beam.state = if keyboard_input.pressed(KeyCode::X) && state.focused {
+            BeamState::Firing
+        } else {
+            BeamState::Off
+        };
+    });
+}
+
+fn enemy_beam_cycle (
+    time: Res<GameTime>,
+    query: Query<(&mut BeamEmitter, &mut BeamCycle, &AIState, &AIBehavior), With<AI>>,
+) {
+    query.for_each_mut(|(mut beam, mut cycle, state, behavior)| {
+        if !behavior.guns_active(state) {
+            beam.state = BeamState::Off;
+            cycle.step = 0.;
+            return;
+        }
+        cycle.step = (cycle.step + time.delta().as_secs_f32())
+            % (cycle.telegraph + cycle.firing + cycle.cooldown);
+        beam.state = if cycle.step < cycle.telegraph {
+            BeamState::Telegraph
+        } else if cycle.step < cycle.telegraph + cycle.firing {
+            BeamState::Firing
+        } else {
+            BeamState::Off
+        };
+    });
+}
+
+fn player_beam_hit (
+    time: Res<GameTime>,
+    emitter_query: Query<(&Transform, &mut BeamEmitter), With<Player>>,
+    mut target_query: Query<(Entity, &GlobalTransform, &HitBox, &mut Health, Option<&Shielded>), With<AI>>,
+    blocker_query: Query<(&GlobalTransform, &HitBox), With<BeamBlocker>>,
+) {
+    let delta = time.delta().as_secs_f32();
+    emitter_query.for_each_mut(|(transform, mut beam)| {
+        if beam.state == BeamState::Off {
+            return;
+        }
+        let from = (transform.translation + beam.offset).truncate();
+        let blocked = beam_raycast(&beam, from, blocker_query.iter());
+        let mut closest: Option<(Entity, f32)> = None;
+        target_query.for_each_mut(|(entity, target_transform, hitbox, _, _)| {
+            let hit = hitbox.raycast(&target_transform.translation, from, beam.direction, beam.width / 2.);
+            if let Some(distance) = hit {
+                if distance < blocked && closest.map_or(true, |(_, closest)| distance < closest) {
+                    closest = Some((entity, distance));
+                }
+            }
+        });
+        beam.length = match closest {
+            Some((entity, distance)) => {
+                if beam.state == BeamState::Firing {
+                    if let Ok((_, _, _, mut health, None)) = target_query.get_mut(entity) {
+                        health.0 -= beam.dps * delta;
+                    }
+                }
+                distance
+            },
+            None => blocked,
+        };
+    });
+}
+
+fn enemy_beam_hit (
+    time: Res<GameTime>,
+    emitter_query: Query<(&Transform, &mut BeamEmitter), With<AI>>,
+    target_query: Query<(&Transform, &HitBox, &mut Health, Option<&Invulnerable>), With<Player>>,
+    blocker_query: Query<(&GlobalTransform, &HitBox), With<BeamBlocker>>,
+) {
+    let delta = time.delta().as_secs_f32();
+    emitter_query.for_each_mut(|(transform, mut beam)| {
+        if beam.state == BeamState::Off {
+            return;
+        }
+        let from = (transform.translation + beam.offset).truncate();
+        beam.length = beam_raycast(&beam, from, blocker_query.iter());
+        target_query.for_each_mut(|(target_transform, hitbox, mut health, invulnerable)| {
+            let hit = hitbox.raycast(&target_transform.translation, from, beam.direction, beam.width / 2.);
+            if let Some(distance) = hit {
+                if distance < beam.length {
+                    beam.length = distance;
+                    if beam.state == BeamState::Firing && invulnerable.is_none() {
+                        health.0 -= beam.dps * delta;
+                    }
+                }
+            }
+        });
+    });
+}
+
+fn beam_sprite_update (
+    mut commands: Commands,
+    materials: Res<BeamMaterials>,
+    emitter_query: Query<(Entity, &Transform, &mut BeamEmitter, Option<&Player>), Without<BeamSprite>>,
+    mut sprite_query: Query<(Entity, &BeamSprite, &mut Transform, &mut Sprite, &mut Handle<ColorMaterial>)>,
+) {
+    // Beams whose owner is gone
+    sprite_query.for_each_mut(|(entity, beam_sprite, _, _, _)| {
+        if emitter_query.get_component::<BeamEmitter>(beam_sprite.owner).is_err() {
+            commands.entity(entity).despawn_recursive();
+        }
+    });
+
+    emitter_query.for_each_mut(|(entity, transform, mut beam, player)| {
+        if beam.state == BeamState::Off {
+            if let Some(sprite) = beam.sprite.take() {
+                commands.entity(sprite).despawn_recursive();
+            }
+            return;
+        }
+
+        let (material, width) = match (beam.state, player.is_some()) {
+            (BeamState::Telegraph, _) => (&materials.telegraph, 2.),
+            (_, true) => (&materials.player, beam.width),
+            (_, false) => (&materials.enemy, beam.width),
+        };
+        // Sprite stretched along the beam, centered halfway to the hit
+        let from = transform.translation + beam.offset;
+        let center = from + (beam.direction * beam.length / 2.).extend(0.);
+        let rotation = Quat::from_rotation_z(beam.direction.y.atan2(beam.direction.x) - std::f32::consts::FRAC_PI_2);
+        let size = Vec2::new(width, beam.length);
+        let translation = Vec3::new(center.x, center.y, 5.);
+
+        let existing = beam.sprite.and_then(|sprite| sprite_query.get_mut(sprite).ok());
+        match existing {
+            Some((_, _, mut sprite_transform, mut sprite, mut sprite_material)) => {
+                sprite_transform.translation = translation;
+                sprite_transform.rotation = rotation;
+                sprite.size = size;
+                if *sprite_material != *material {
+                    *sprite_material = material.clone();
+                }
+            },
+            None => {
+                let sprite = commands
+                    .spawn_bundle(SpriteBundle {
+                        material: material.clone(),
+                        sprite: Sprite::new(size),
+                        transform: Transform {
+                            translation,
+                            rotation,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .insert(BeamSprite { owner: entity })
+                    .id();
+                beam.sprite = Some(sprite);
+            }
+        }
+    });
+}
+
+pub struct BeamPlugin;
+impl Plugin for BeamPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .add_startup_system(beam_setup.system())
+            .add_system(player_beam_input.system())
+            .add_system(enemy_beam_cycle.system())
+            .add_system(player_beam_hit.system())
+            .add_system(enemy_beam_hit.system())
+            .add_system(beam_sprite_update.system());
+    }
+}
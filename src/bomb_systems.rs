@@ -0,0 +1,167 @@
+use bevy::{core::Timer, input::Input, math::{Vec2, Vec3}, prelude::{Assets, Commands, Entity, Handle, HorizontalAlign, IntoSystem, KeyCode, ParallelSystemDescriptorCoercion, Plugin, Query, Res, ResMut, SpriteBundle, Transform, VerticalAlign, With, Without}, render::color::Color, sprite::{ColorMaterial, Sprite}, text::{Text, Text2dBundle, TextAlignment, TextStyle}};
+
+use crate::{enemy_systems::AI, game_systems::{GameState, GameTime}, health_systems::Invulnerable, laser_systems::{FromEnemy, Laser, LaserPool}, part_systems::Shielded, player_systems::{PLAYER_DEATH, Player}, util::{Health, Materials, WinSize}};
+
+//#region Components
+/** First is current bombs, second is max bombs */
+pub struct Bombs(pub u32, pub u32);
+impl Bombs {
+    /** Refill bombs without going over the max, used by pickups */
+    pub fn add(&mut self, amount: u32) {
+        self.0 = (self.0 + amount).min(self.1);
+    }
+}
+impl Default for Bombs {
+    fn default() -> Self {
+        Self (3, 5)
+    }
+}
+
+pub struct BombBlast {
+    pub timer: Timer,
+}
+
+pub struct BombText;
+//#endregion
+
+//#region Resources
+pub struct BombConfig {
+    /** Damage dealt to every enemy on screen */
+    pub damage: f32,
+    /** Seconds of invulnerability granted to the player */
+    pub invulnerability: f32,
+    /** Score given per enemy laser cleared, 0 to disable */
+    pub laser_score: u32,
+}
+impl Default for BombConfig {
+    fn default() -> Self {
+        Self {
+            damage: 10.,
+            invulnerability: 3.,
+            laser_score: 10,
+        }
+    }
+}
+//#endregion
+
+fn spawn_bomb_ui (
+    mut commands: Commands,
+    assets: Res<Materials>,
+    win_size: Res<WinSize>,
+) {
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 28.,
+                    ..Default::default()
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Top,
+                    horizontal: HorizontalAlign::Left,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(win_size.w - 15., 15., 69.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(BombText);
+}
+
+fn bomb_use (
+    mut commands: Commands,
+    mut pool: ResMut<LaserPool>,
+    keyboard_input: Res<Input<KeyCode>>,
+    config: Res<BombConfig>,
+    mut game_state: ResMut<GameState>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    player_query: Query<(Entity, &Transform, &mut Bombs), With<Player>>,
+    laser_query: Query<Entity, (With<Laser>, With<FromEnemy>)>,
+    enemy_query: Query<&mut Health, (With<AI>, Without<Shielded>)>,
+    blast_query: Query<&BombBlast>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Z) || blast_query.iter().next().is_some() {
+        return;
+    }
+    player_query.for_each_mut(|(player_entity, transform, mut bombs)| {
+        if bombs.0 == 0 {
+            return;
+        }
+        bombs.0 -= 1;
+
+        // Clear the screen of enemy lasers
+        laser_query.for_each(|laser_entity| {
+            pool.release(&mut commands, laser_entity);
+            game_state.score += config.laser_score;
+        });
+        enemy_query.for_each_mut(|mut health| {
+            health.0 -= config.damage;
+        });
+
+        commands
+            .entity(player_entity)
+            .insert(Invulnerable::from_seconds(config.invulnerability));
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: materials.add(Color::rgba(1., 1., 1., 0.8).into()),
+                sprite: Sprite::new(Vec2::new(32., 32.)),
+                transform: Transform {
+                    translation: Vec3::new(transform.translation.x, transform.translation.y, 50.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(BombBlast {
+                timer: Timer::from_seconds(0.75, false),
+            });
+    });
+}
+
+fn bomb_blast_update (
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    query: Query<(Entity, &mut BombBlast, &mut Transform, &Handle<ColorMaterial>)>,
+) {
+    query.for_each_mut(|(entity, mut blast, mut transform, material)| {
+        blast.timer.tick(time.delta());
+        // Grow until it covers the screen while fading out
+        let t = blast.timer.percent();
+        transform.scale = Vec3::new(1. + t * 40., 1. + t * 40., 1.);
+        if let Some(material) = materials.get_mut(material) {
+            material.color.set_a(0.8 * (1. - t));
+        }
+        if blast.timer.finished() {
+            commands.entity(entity).despawn();
+        }
+    });
+}
+
+fn update_bomb_ui (
+    query: Query<&Bombs, With<Player>>,
+    text_query: Query<(&mut Text, With<BombText>)>,
+) {
+    let bombs = match query.iter().next() {
+        Some(bombs) => bombs.0,
+        None => 0,
+    };
+    text_query.for_each_mut(|(mut text, _)| {
+        text.sections.get_mut(0).unwrap().value = ["Bombs ".to_owned(), bombs.to_string()].concat();
+    });
+}
+
+pub struct BombPlugin;
+impl Plugin for BombPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .insert_resource(BombConfig::default())
+            .add_startup_system_to_stage("game_setup_ui", spawn_bomb_ui.system())
+            .add_system(bomb_use.system().before(PLAYER_DEATH))
+            .add_system(bomb_blast_update.system())
+            .add_system(update_bomb_ui.system());
+    }
+}
@@ -0,0 +1,315 @@
+use std::f32::consts::PI;
+
+use bevy::{math::{Vec2, Vec3}, prelude::{Assets, Commands, DespawnRecursiveExt, Entity, EventReader, Handle, HorizontalAlign, IntoSystem, Plugin, Query, Res, ResMut, SpriteBundle, SpriteSheetBundle, Transform, VerticalAlign, With}, render::color::Color, sprite::{ColorMaterial, Sprite, TextureAtlasSprite}, text::{Text, Text2dBundle, TextAlignment, TextStyle}};
+
+use crate::{collision_systems::ContactDamage, daily_systems::DailyModifiers, difficulty_systems::{Difficulty, DifficultySettings}, enemy_systems::{AIBehavior, AIHorizontal, AIMoveStates, AIMovement, AIStateConfig, AIState, EnemyBundle}, game_systems::{GameState, StageStatus}, gun_systems::{Gun, GunAim, GunCollection, GunCooldown}, health_systems::EnemyDestroyed, laser_systems::{FromEnemy, Laser, LaserPool}, modifier_systems::Split, util::{Health, HitBox, Materials, Speed, WinSize}};
+
+const BOSS_HEALTH: f32 = 300.;
+const BOSS_SCORE: u32 = 10000;
+const BOSS_BAR_HEIGHT: f32 = 8.;
+
+//#region Components
+pub struct BossPhase {
+    /** Health ratio at or below which the phase starts */
+    pub threshold: f32,
+    pub movement: AIMovement,
+    /** Guns of the phase, with as many bullets as the difficulty asks for */
+    pub guns: fn(&DifficultySettings) -> Box<[Gun]>,
+}
+
+pub struct Boss {
+    pub phases: Vec<BossPhase>,
+    pub current: usize,
+}
+
+pub struct BossBar;
+pub struct BossBarFill;
+pub struct StageClearText;
+//#endregion
+
+pub struct BossMaterials {
+    pub bar: Handle<ColorMaterial>,
+    pub fill: Handle<ColorMaterial>,
+}
+
+fn boss_setup (
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(BossMaterials {
+        bar: materials.add(Color::rgba(0.2, 0.2, 0.2, 0.8).into()),
+        fill: materials.add(Color::ORANGE_RED.into()),
+    });
+}
+
+//#region Bullet patterns
+/** Aimed shots side by side, three on normal */
+fn aimed_volley(settings: &DifficultySettings) -> Box<[Gun]> {
+    let count = settings.bullets(3);
+    (0..count)
+        .map(|i| Gun {
+            cooldown: GunCooldown(0.5, 1.2),
+            initial_speed: Speed(0., -300.),
+            aim: GunAim::AtPlayer,
+            offset: Vec3::new((i as f32 - (count - 1) as f32 / 2.) * 20., 0., 0.),
+            inaccuracy: 0.1,
+            ..Default::default()
+        })
+        .collect()
+}
+
+/** Ring of shots all around the boss, twelve on normal */
+fn ring(settings: &DifficultySettings) -> Box<[Gun]> {
+    let count = settings.bullets(12);
+    (0..count)
+        .map(|i| Gun {
+            cooldown: GunCooldown(1., 1.6),
+            initial_speed: Speed(0., -220.),
+            aim: GunAim::Facing(i as f32 * PI * 2. / count as f32),
+            inaccuracy: 0.15,
+            ..Default::default()
+        })
+        .collect()
+}
+
+/** Led shots, side by side past normal, with bursting shells */
+fn frenzy(settings: &DifficultySettings) -> Box<[Gun]> {
+    let led = Gun {
+        cooldown: GunCooldown(0., 0.4),
+        initial_speed: Speed(0., -380.),
+        aim: GunAim::Lead,
+        inaccuracy: 0.2,
+        ..Default::default()
+    };
+    let mut guns = settings.volley(&led, 20.);
+    // Its fragments are scaled with the rest of the gun
+    guns.push(Gun {
+        cooldown: GunCooldown(1., 2.),
+        initial_speed: Speed(0., -180.),
+        split: Some(Split::after(8, PI * 2., 1.)),
+        ..Default::default()
+    });
+    guns.into_boxed_slice()
+}
+//#endregion
+
+fn boss_phases() -> Vec<BossPhase> {
+    vec![
+        BossPhase {
+            threshold: 1.,
+            movement: AIMovement::Horizontal,
+            guns: aimed_volley,
+        },
+        BossPhase {
+            threshold: 0.66,
+            movement: AIMovement::Hold,
+            guns: ring,
+        },
+        BossPhase {
+            threshold: 0.33,
+            movement: AIMovement::Horizontal,
+            guns: frenzy,
+        },
+    ]
+}
+
+/** Slides down into view, then stays on screen until destroyed */
+fn boss_behavior(movement: AIMovement) -> AIBehavior {
+    AIBehavior {
+        entering: AIStateConfig {
+            duration: 2.5,
+            movement: AIMovement::Straight(Vec2::new(0., -80.)),
+            guns: false,
+            next: AIMoveStates::Hovering,
+        },
+        hovering: AIStateConfig {
+            duration: f32::INFINITY,
+            movement,
+            guns: true,
+            next: AIMoveStates::Hovering,
+        },
+        lifetime: None,
+        ..Default::default()
+    }
+}
+
+fn boss_spawn (
+    mut commands: Commands,
+    difficulty: Res<Difficulty>,
+    modifiers: Res<DailyModifiers>,
+    assets: Res<Materials>,
+    boss_materials: Res<BossMaterials>,
+    mut game_state: ResMut<GameState>,
+    win_size: Res<WinSize>,
+) {
+    let distance = &game_state.distance;
+    if game_state.status != StageStatus::Running || distance.0 < distance.1 {
+        return;
+    }
+    let phases = boss_phases();
+    let first = &phases[0];
+    let boss = commands
+        .spawn_bundle(EnemyBundle {
+            sprite: SpriteSheetBundle {
+                texture_atlas: assets.enemy_atlas.clone(),
+                sprite: TextureAtlasSprite {
+                    index: 0,
+                    ..Default::default()
+                },
+                transform: Transform {
+                    translation: Vec3::new(win_size.half_w, win_size.h + 100., 10.),
+                    scale: Vec3::new(8., 8., 1.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            speed: Speed(0., 60.),
+            behavior: boss_behavior(first.movement),
+            weapon: GunCollection {
+                guns: (first.guns)(&modifiers.settings(*difficulty)),
+            },
+            health: Health(BOSS_HEALTH, BOSS_HEALTH),
+            hitbox: HitBox {
+                rect: Vec3::new(100., 100., 1.)
+            },
+            contact: ContactDamage(40.),
+            ..Default::default()
+        })
+        .insert(AIHorizontal {
+            x_origin: win_size.half_w,
+            y_origin: win_size.h - 150.,
+            amplitude: win_size.half_w - 150.,
+            frequency: 0.6,
+        })
+        .insert(Boss {
+            phases,
+            current: 0,
+        })
+        .id();
+    game_state.status = StageStatus::Boss(boss);
+
+    // Health bar across the top of the screen
+    let width = win_size.w - 100.;
+    commands
+        .spawn_bundle(SpriteBundle {
+            material: boss_materials.bar.clone(),
+            sprite: Sprite::new(Vec2::new(width, BOSS_BAR_HEIGHT)),
+            transform: Transform::from_xyz(win_size.half_w, win_size.h - 50., 60.),
+            ..Default::default()
+        })
+        .insert(BossBar);
+    commands
+        .spawn_bundle(SpriteBundle {
+            material: boss_materials.fill.clone(),
+            sprite: Sprite::new(Vec2::new(width, BOSS_BAR_HEIGHT)),
+            transform: Transform::from_xyz(win_size.half_w, win_size.h - 50., 61.),
+            ..Default::default()
+        })
+        .insert(BossBarFill);
+}
+
+fn boss_phase_update (
+    mut commands: Commands,
+    difficulty: Res<Difficulty>,
+    modifiers: Res<DailyModifiers>,
+    mut pool: ResMut<LaserPool>,
+    query: Query<(&Health, &mut Boss, &mut AIBehavior, &mut GunCollection, &AIState)>,
+    laser_query: Query<Entity, (With<Laser>, With<FromEnemy>)>,
+) {
+    query.for_each_mut(|(health, mut boss, mut behavior, mut guns, state)| {
+        let ratio = health.0 / health.1;
+        let next = boss.current + 1;
+        let phase = match boss.phases.get(next) {
+            Some(phase) if ratio <= phase.threshold => phase,
+            _ => return,
+        };
+        behavior.hovering.movement = phase.movement;
+        // Spawned guns get scaled with the boss, these come later
+        let settings = modifiers.settings(*difficulty);
+        guns.guns = (phase.guns)(&settings);
+        for gun in guns.guns.iter_mut() {
+            settings.scale_gun(gun);
+        }
+        boss.current = next;
+        // Clear the screen so the new pattern starts fresh
+        if state.movement != AIMoveStates::Entering {
+            laser_query.for_each(|laser| {
+                pool.release(&mut commands, laser);
+            });
+        }
+    });
+}
+
+fn boss_bar_update (
+    win_size: Res<WinSize>,
+    boss_query: Query<&Health, With<Boss>>,
+    bar_query: Query<&mut Transform, With<BossBarFill>>,
+) {
+    let ratio = match boss_query.iter().next() {
+        Some(health) => (health.0 / health.1).clamp(0., 1.),
+        None => return,
+    };
+    let width = win_size.w - 100.;
+    bar_query.for_each_mut(|mut transform| {
+        transform.scale.x = ratio;
+        transform.translation.x = win_size.half_w - width * (1. - ratio) / 2.;
+    });
+}
+
+fn boss_defeat (
+    mut commands: Commands,
+    mut pool: ResMut<LaserPool>,
+    assets: Res<Materials>,
+    win_size: Res<WinSize>,
+    mut game_state: ResMut<GameState>,
+    mut destroyed: EventReader<EnemyDestroyed>,
+    bar_query: Query<Entity, With<BossBar>>,
+    fill_query: Query<Entity, With<BossBarFill>>,
+    laser_query: Query<Entity, (With<Laser>, With<FromEnemy>)>,
+) {
+    let boss = match game_state.status {
+        StageStatus::Boss(boss) => boss,
+        _ => return,
+    };
+    if !destroyed.iter().any(|event| event.entity == boss) {
+        return;
+    }
+    game_state.status = StageStatus::Cleared;
+    game_state.score += BOSS_SCORE;
+    bar_query.for_each(|entity| commands.entity(entity).despawn_recursive());
+    fill_query.for_each(|entity| commands.entity(entity).despawn_recursive());
+    laser_query.for_each(|laser| pool.release(&mut commands, laser));
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "STAGE CLEAR",
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 48.,
+                    ..Default::default()
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(win_size.half_w, win_size.half_h, 69.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(StageClearText);
+}
+
+pub struct BossPlugin;
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .add_startup_system(boss_setup.system())
+            .add_system(boss_spawn.system())
+            .add_system(boss_phase_update.system())
+            .add_system(boss_bar_update.system())
+            .add_system(boss_defeat.system());
+    }
+}
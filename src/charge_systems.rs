@@ -0,0 +1,141 @@
+use bevy::{input::Input, math::{Vec2, Vec3}, prelude::{Assets, BuildChildren, Children, Commands, DespawnRecursiveExt, Entity, Handle, IntoSystem, KeyCode, Plugin, Query, Res, ResMut, SpriteBundle, SpriteSheetBundle, Transform, With, Without}, render::color::Color, sprite::{ColorMaterial, Sprite, TextureAtlasSprite}};
+
+use crate::{game_systems::GameTime, laser_systems::{Damage, FromPlayer, LaserBundle, LaserPool, LaserRadius, Piercing}, player_systems::Player, util::{Materials, Speed}};
+
+/** Charge needed before releasing fires anything */
+const MIN_CHARGE: f32 = 0.25;
+const CHARGE_METER_WIDTH: f32 = 24.;
+
+//#region Components
+/** First is current charge in seconds, second is max charge */
+pub struct Charge(pub f32, pub f32);
+impl Charge {
+    pub fn ratio(&self) -> f32 {
+        (self.0 / self.1).clamp(0., 1.)
+    }
+}
+impl Default for Charge {
+    fn default() -> Self {
+        Self (0., 2.)
+    }
+}
+
+pub struct ChargeMeter;
+//#endregion
+
+pub struct ChargeMaterials {
+    pub meter: Handle<ColorMaterial>,
+    pub full: Handle<ColorMaterial>,
+}
+
+fn charge_setup (
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(ChargeMaterials {
+        meter: materials.add(Color::CYAN.into()),
+        full: materials.add(Color::WHITE.into()),
+    });
+}
+
+fn charge_update (
+    mut commands: Commands,
+    mut pool: ResMut<LaserPool>,
+    keyboard_input: Res<Input<KeyCode>>,
+    time: Res<GameTime>,
+    materials: Res<Materials>,
+    charge_materials: Res<ChargeMaterials>,
+    player_query: Query<(Entity, &Transform, &mut Charge, Option<&Children>), With<Player>>,
+    meter_query: Query<Entity, With<ChargeMeter>>,
+) {
+    player_query.for_each_mut(|(player_entity, transform, mut charge, children)| {
+        let meter = children.and_then(|children| {
+            children.iter().find(|child| meter_query.get(**child).is_ok()).cloned()
+        });
+
+        if keyboard_input.pressed(KeyCode::V) {
+            charge.0 = (charge.0 + time.delta().as_secs_f32()).min(charge.1);
+            if meter.is_none() && charge.0 >= MIN_CHARGE {
+                commands.entity(player_entity).with_children(|parent| {
+                    parent
+                        .spawn_bundle(SpriteBundle {
+                            material: charge_materials.meter.clone(),
+                            sprite: Sprite::new(Vec2::new(CHARGE_METER_WIDTH, 2.)),
+                            transform: Transform::from_xyz(0., -20., 1.),
+                            ..Default::default()
+                        })
+                        .insert(ChargeMeter);
+                });
+            }
+            return;
+        }
+
+        if let Some(meter) = meter {
+            commands.entity(meter).despawn_recursive();
+        }
+        if charge.0 < MIN_CHARGE {
+            charge.0 = 0.;
+            return;
+        }
+
+        // Released, bigger and stronger the longer it was held
+        let ratio = charge.ratio();
+        let size = 2. + ratio * 4.;
+        pool
+            .spawn(&mut commands, LaserBundle {
+                sprite: SpriteSheetBundle {
+                    texture_atlas: materials.projectile_atlas.clone(),
+                    sprite: TextureAtlasSprite {
+                        index: 1,
+                        ..Default::default()
+                    },
+                    transform: Transform {
+                        translation: Vec3::new(transform.translation.x, transform.translation.y + 20., 0.),
+                        scale: Vec3::new(size, size, 1.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                speed: Speed(0., 700.),
+                damage: Damage(2. + ratio * 18., 2. + ratio * 18.),
+                ..Default::default()
+            })
+            .insert(FromPlayer)
+            .insert(Piercing::default())
+            .insert(LaserRadius(size * 4.));
+        charge.0 = 0.;
+    });
+}
+
+fn charge_meter_update (
+    charge_materials: Res<ChargeMaterials>,
+    player_query: Query<&Charge, With<Player>>,
+    meter_query: Query<(&mut Transform, &mut Handle<ColorMaterial>), (With<ChargeMeter>, Without<Player>)>,
+) {
+    let ratio = match player_query.iter().next() {
+        Some(charge) => charge.ratio(),
+        None => return,
+    };
+    meter_query.for_each_mut(|(mut transform, mut material)| {
+        transform.scale.x = ratio;
+        transform.translation.x = (ratio - 1.) * CHARGE_METER_WIDTH / 2.;
+        let wanted = if ratio >= 1. {
+            &charge_materials.full
+        } else {
+            &charge_materials.meter
+        };
+        if *material != *wanted {
+            *material = wanted.clone();
+        }
+    });
+}
+
+pub struct ChargePlugin;
+impl Plugin for ChargePlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .add_startup_system(charge_setup.system())
+            .add_system(charge_update.system())
+            .add_system(charge_meter_update.system());
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::{diagnostic::{Diagnostic, DiagnosticId, Diagnostics}, prelude::{Commands, DespawnRecursiveExt, Entity, IntoSystem, Plugin, Query, Res, ResMut, Transform}};
+
+use crate::{game_systems::GameTime, laser_systems::LaserPool, pool::Pooled, util::{Lifetime, WinSize}};
+
+pub const DESPAWNABLE_COUNT: DiagnosticId = DiagnosticId::from_u128(0x8f1c_3a6e_5d2b_4c07_9e41_b6a2_d3f8_1075);
+pub const OFFSCREEN_CULLED: DiagnosticId = DiagnosticId::from_u128(0x2b7d_94c1_e06a_4f53_8c1e_5a3f_70b9_d462);
+pub const EXPIRED_CULLED: DiagnosticId = DiagnosticId::from_u128(0x61e3_0fa8_3b5c_47d9_a274_c9e1_58d6_0b3a);
+
+//#region Components
+/** Despawned once it's further than `margin` outside of the screen, only for top level entities */
+pub struct Despawnable {
+    pub margin: f32,
+    /** Set once it's been on screen, things spawning outside aren't culled until they came in */
+    pub entered: bool,
+}
+impl Despawnable {
+    /** Culled as soon as it leaves, for things spawning on screen */
+    pub fn new(margin: f32) -> Self {
+        Self {
+            margin,
+            entered: true,
+        }
+    }
+    /** Culled only after it's been on screen once */
+    pub fn after_entering(margin: f32) -> Self {
+        Self {
+            margin,
+            entered: false,
+        }
+    }
+}
+//#endregion
+
+/** Totals since the start, also sent to the diagnostics every frame */
+#[derive(Default)]
+pub struct CleanupStats {
+    pub offscreen: u32,
+    pub expired: u32,
+}
+
+fn cleanup_setup (
+    mut diagnostics: ResMut<Diagnostics>,
+) {
+    diagnostics.add(Diagnostic::new(DESPAWNABLE_COUNT, "despawnable_count", 20));
+    diagnostics.add(Diagnostic::new(OFFSCREEN_CULLED, "offscreen_culled", 20));
+    diagnostics.add(Diagnostic::new(EXPIRED_CULLED, "expired_culled", 20));
+}
+
+/** Pooled entities go back to the pool, only lasers are pooled for now */
+fn despawn(commands: &mut Commands, pool: &mut LaserPool, entity: Entity, pooled: Option<&Pooled>) {
+    match pooled {
+        Some(_) => pool.release(commands, entity),
+        None => commands.entity(entity).despawn_recursive(),
+    }
+}
+
+fn offscreen_cleanup (
+    mut commands: Commands,
+    mut pool: ResMut<LaserPool>,
+    win_size: Res<WinSize>,
+    mut stats: ResMut<CleanupStats>,
+    mut diagnostics: ResMut<Diagnostics>,
+    query: Query<(Entity, &Transform, &mut Despawnable, Option<&Pooled>)>,
+) {
+    let mut count = 0;
+    query.for_each_mut(|(entity, transform, mut despawnable, pooled)| {
+        count += 1;
+        let (x, y) = (transform.translation.x, transform.translation.y);
+        let outside = |margin: f32| {
+            x < -margin || x > win_size.w + margin || y < -margin || y > win_size.h + margin
+        };
+        if !despawnable.entered {
+            despawnable.entered = !outside(0.);
+            return;
+        }
+        if outside(despawnable.margin) {
+            despawn(&mut commands, &mut pool, entity, pooled);
+            stats.offscreen += 1;
+        }
+    });
+    diagnostics.add_measurement(DESPAWNABLE_COUNT, count as f64);
+    diagnostics.add_measurement(OFFSCREEN_CULLED, stats.offscreen as f64);
+}
+
+fn lifetime_cleanup (
+    mut commands: Commands,
+    mut pool: ResMut<LaserPool>,
+    time: Res<GameTime>,
+    mut stats: ResMut<CleanupStats>,
+    mut diagnostics: ResMut<Diagnostics>,
+    query: Query<(Entity, &mut Lifetime, Option<&Pooled>)>,
+) {
+    query.for_each_mut(|(entity, mut lifetime, pooled)| {
+        if lifetime.0.tick(time.delta()).finished() {
+            despawn(&mut commands, &mut pool, entity, pooled);
+            stats.expired += 1;
+        }
+    });
+    diagnostics.add_measurement(EXPIRED_CULLED, stats.expired as f64);
+}
+
+pub struct CleanupPlugin;
+impl Plugin for CleanupPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .insert_resource(CleanupStats::default())
+            .add_startup_system(cleanup_setup.system())
+            .add_system(offscreen_cleanup.system())
+            .add_system(lifetime_cleanup.system());
+    }
+}
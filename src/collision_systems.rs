@@ -1,9 +1,43 @@
-use bevy::prelude::Plugin;
+use bevy::prelude::{Commands, Entity, GlobalTransform, IntoSystem, ParallelSystemDescriptorCoercion, Plugin, Query, Transform, With, Without};
+
+use crate::{enemy_systems::AI, health_systems::Invulnerable, part_systems::Shielded, player_systems::{PLAYER_DEATH, Player}, util::{Health, HitBox}};
+
+/** Seconds the player can't be hurt after bumping into something */
+const CONTACT_INVULNERABILITY: f32 = 1.;
+
+//#region Components
+/** Damage dealt to whatever of the other side this body runs into */
+pub struct ContactDamage(pub f32);
+//#endregion
+
+fn body_collision (
+    mut commands: Commands,
+    player_query: Query<(Entity, &mut Health, &HitBox, &Transform, &ContactDamage), (With<Player>, Without<Invulnerable>)>,
+    enemy_query: Query<(&mut Health, &HitBox, &GlobalTransform, &ContactDamage, Option<&Shielded>), (With<AI>, Without<Player>)>,
+) {
+    player_query.for_each_mut(|(player_entity, mut player_health, player_hitbox, player_transform, player_contact)| {
+        let mut hit = false;
+        enemy_query.for_each_mut(|(mut health, hitbox, transform, contact, shielded)| {
+            if hit || !hitbox.intersects(&transform.translation, player_hitbox, &player_transform.translation) {
+                return;
+            }
+            hit = true;
+            if shielded.is_none() {
+                health.0 -= player_contact.0;
+            }
+            player_health.0 -= contact.0;
+        });
+        if hit {
+            commands.entity(player_entity).insert(Invulnerable::from_seconds(CONTACT_INVULNERABILITY));
+        }
+    });
+}
 
 pub struct CollisionPlugin;
 
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut bevy::prelude::AppBuilder) {
-        app;
+        app
+            .add_system(body_collision.system().before(PLAYER_DEATH));
     }
-}
\ No newline at end of file
+}
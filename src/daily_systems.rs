@@ -0,0 +1,168 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::{EventReader, IntoSystem, Plugin, Query, Res, ResMut, With};
+
+use crate::{difficulty_systems::{Difficulty, DifficultySettings}, game_systems::{GameMode, RunStarted}, player_systems::{Lives, MAX_POWER_LEVEL, Player, PowerLevel}, util::{Random, Rng}};
+
+const SECONDS_PER_DAY: u64 = 86400;
+/** Mixed with the day so the seed doesn't follow the calendar */
+const DAILY_STREAM: u64 = 0xda11;
+const MODIFIERS_PER_DAY: usize = 2;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DailyModifier {
+    ToughEnemies,
+    FastBullets,
+    RapidFire,
+    BulletCurtains,
+    OneLife,
+    FullPower,
+}
+impl DailyModifier {
+    pub const ALL: [DailyModifier; 6] = [
+        DailyModifier::ToughEnemies,
+        DailyModifier::FastBullets,
+        DailyModifier::RapidFire,
+        DailyModifier::BulletCurtains,
+        DailyModifier::OneLife,
+        DailyModifier::FullPower,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DailyModifier::ToughEnemies => "Tough enemies",
+            DailyModifier::FastBullets => "Fast bullets",
+            DailyModifier::RapidFire => "Rapid fire",
+            DailyModifier::BulletCurtains => "Bullet curtains",
+            DailyModifier::OneLife => "One life",
+            DailyModifier::FullPower => "Full power",
+        }
+    }
+}
+
+/** Challenge of the day, the same for everyone playing on that date */
+pub struct Daily {
+    /** Days since 1970, in UTC so the day changes at the same time everywhere */
+    pub day: u64,
+    pub seed: u64,
+    pub modifiers: Vec<DailyModifier>,
+}
+impl Daily {
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self::new(seconds / SECONDS_PER_DAY)
+    }
+
+    pub fn new(day: u64) -> Self {
+        let seed = Random::stream(day, DAILY_STREAM).0;
+        let mut random = Random::new(seed);
+        let mut modifiers = Vec::new();
+        while modifiers.len() < MODIFIERS_PER_DAY {
+            let modifier = DailyModifier::ALL[random.next_u32() as usize % DailyModifier::ALL.len()];
+            if !modifiers.contains(&modifier) {
+                modifiers.push(modifier);
+            }
+        }
+        Self { day, seed, modifiers }
+    }
+
+    /** `YYYY-MM-DD`, from the days since 1970 */
+    pub fn date(&self) -> String {
+        // Howard Hinnant's civil_from_days, with the year starting in March
+        let z = self.day + 719468;
+        let era = z / 146097;
+        let doe = z % 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+
+    pub fn modifier_names(&self) -> String {
+        self.modifiers.iter().map(|modifier| modifier.name()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/** Modifiers of the run being played, none outside of the daily challenge */
+#[derive(Default)]
+pub struct DailyModifiers(pub Vec<DailyModifier>);
+impl DailyModifiers {
+    /** Settings of `difficulty` with the modifiers on top */
+    pub fn settings(&self, difficulty: Difficulty) -> DifficultySettings {
+        let mut settings = difficulty.settings();
+        for modifier in self.0.iter() {
+            match modifier {
+                DailyModifier::ToughEnemies => settings.health *= 1.5,
+                DailyModifier::FastBullets => settings.bullet_speed *= 1.25,
+                DailyModifier::RapidFire => settings.fire_rate *= 1.3,
+                DailyModifier::BulletCurtains => settings.bullet_count *= 1.5,
+                DailyModifier::OneLife | DailyModifier::FullPower => (),
+            }
+        }
+        settings
+    }
+}
+
+/** Same seed, difficulty and modifiers for everyone on the day */
+fn daily_start (
+    mut started: EventReader<RunStarted>,
+    mode: Res<GameMode>,
+    daily: Res<Daily>,
+    mut rng: ResMut<Rng>,
+    mut difficulty: ResMut<Difficulty>,
+    mut modifiers: ResMut<DailyModifiers>,
+    query: Query<(&mut Lives, &mut PowerLevel), With<Player>>,
+) {
+    if started.iter().next().is_none() || *mode != GameMode::Daily {
+        return;
+    }
+    *rng = Rng::new(daily.seed);
+    *difficulty = Difficulty::Normal;
+    modifiers.0 = daily.modifiers.clone();
+    query.for_each_mut(|(mut lives, mut power)| {
+        if modifiers.0.contains(&DailyModifier::OneLife) {
+            lives.0 = 0;
+        }
+        if modifiers.0.contains(&DailyModifier::FullPower) {
+            power.0 = MAX_POWER_LEVEL;
+        }
+    });
+}
+
+pub struct DailyPlugin;
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .insert_resource(Daily::today())
+            .insert_resource(DailyModifiers::default())
+            .add_system(daily_start.system());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_from_days() {
+        assert_eq!(Daily::new(0).date(), "1970-01-01");
+        assert_eq!(Daily::new(59).date(), "1970-03-01");
+        assert_eq!(Daily::new(19782).date(), "2024-02-29");
+        assert_eq!(Daily::new(19783).date(), "2024-03-01");
+    }
+
+    #[test]
+    fn same_day_same_challenge() {
+        let (first, second) = (Daily::new(19782), Daily::new(19782));
+        assert_eq!(first.seed, second.seed);
+        assert_eq!(first.modifiers, second.modifiers);
+        assert_eq!(first.modifiers.len(), MODIFIERS_PER_DAY);
+        assert_ne!(first.modifiers[0], first.modifiers[1]);
+        assert_ne!(first.seed, Daily::new(19783).seed);
+    }
+}
@@ -0,0 +1,204 @@
+use bevy::{math::Vec3, prelude::{Added, CoreStage, IntoSystem, Local, Plugin, Query, Res, ResMut, With}};
+
+use crate::{bomb_systems::Bombs, boss_systems::Boss, daily_systems::DailyModifiers, enemy_systems::AI, game_systems::{GameState, GameTime}, gun_systems::{Gun, GunCollection}, player_systems::{Lives, Player, PowerLevel}, util::Health};
+
+/** Rank gained per second survived */
+const RANK_PER_SECOND: f32 = 0.003;
+/** Extra rank per second for each power level */
+const RANK_PER_POWER_SECOND: f32 = 0.001;
+const RANK_PER_POINT: f32 = 0.00002;
+const RANK_DEATH: f32 = 0.25;
+const RANK_BOMB: f32 = 0.1;
+/** Distance between the guns of a regular enemy firing more than one bullet */
+const VOLLEY_SPACING: f32 = 16.;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Lunatic,
+}
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Lunatic];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Lunatic => "Lunatic",
+        }
+    }
+
+    pub fn settings(&self) -> DifficultySettings {
+        match self {
+            Difficulty::Easy => DifficultySettings {
+                health: 0.7,
+                bullet_speed: 0.8,
+                fire_rate: 0.6,
+                bullet_count: 0.5,
+            },
+            Difficulty::Normal => DifficultySettings::default(),
+            Difficulty::Hard => DifficultySettings {
+                health: 1.3,
+                bullet_speed: 1.15,
+                fire_rate: 1.4,
+                bullet_count: 1.5,
+            },
+            Difficulty::Lunatic => DifficultySettings {
+                health: 1.6,
+                bullet_speed: 1.3,
+                fire_rate: 2.,
+                bullet_count: 2.,
+            },
+        }
+    }
+}
+impl Default for Difficulty {
+    fn default() -> Self {
+        Difficulty::Normal
+    }
+}
+
+/** Multipliers applied to enemies, 1 is the stage as designed */
+pub struct DifficultySettings {
+    pub health: f32,
+    pub bullet_speed: f32,
+    /** Shots per second, divides the gun cooldowns */
+    pub fire_rate: f32,
+    /** Bullets in patterns and fragments of splitting shots */
+    pub bullet_count: f32,
+}
+impl DifficultySettings {
+    /** `count` bullets of a pattern, at least one */
+    pub fn bullets(&self, count: u32) -> u32 {
+        ((count as f32 * self.bullet_count).round() as u32).max(1)
+    }
+
+    /** `gun` repeated side by side, `spacing` apart, once on normal */
+    pub fn volley(&self, gun: &Gun, spacing: f32) -> Vec<Gun> {
+        let count = self.bullets(1);
+        (0..count)
+            .map(|i| Gun {
+                offset: gun.offset + Vec3::new((i as f32 - (count - 1) as f32 / 2.) * spacing, 0., 0.),
+                ..gun.clone()
+            })
+            .collect()
+    }
+
+    pub fn scale_gun(&self, gun: &mut Gun) {
+        gun.cooldown.1 /= self.fire_rate;
+        gun.initial_speed.0 *= self.bullet_speed;
+        gun.initial_speed.1 *= self.bullet_speed;
+        if let Some(split) = &mut gun.split {
+            split.count = self.bullets(split.count);
+        }
+    }
+}
+impl Default for DifficultySettings {
+    fn default() -> Self {
+        Self {
+            health: 1.,
+            bullet_speed: 1.,
+            fire_rate: 1.,
+            bullet_count: 1.,
+        }
+    }
+}
+
+/**
+ * Hidden arcade rank from 0 to 1, on top of the difficulty.
+ * Rises as the player survives, scores and powers up, drops when they die or bomb.
+ */
+#[derive(Default)]
+pub struct Rank(pub f32);
+impl Rank {
+    fn add(&mut self, amount: f32) {
+        self.0 = (self.0 + amount).clamp(0., 1.);
+    }
+    /** Multiplies how often enemies fire */
+    pub fn fire_rate(&self) -> f32 {
+        1. + self.0 * 0.6
+    }
+    pub fn bullet_speed(&self) -> f32 {
+        1. + self.0 * 0.3
+    }
+    /** Multiplies how often enemies show up */
+    pub fn spawn_rate(&self) -> f32 {
+        1. + self.0 * 0.5
+    }
+}
+
+/** What the player had last frame, to tell deaths and bombs apart from the rest */
+#[derive(Default)]
+struct RankTracking {
+    score: u32,
+    lives: u32,
+    bombs: u32,
+}
+
+fn rank_update (
+    time: Res<GameTime>,
+    game_state: Res<GameState>,
+    mut rank: ResMut<Rank>,
+    mut last: Local<RankTracking>,
+    query: Query<(&Lives, &Bombs, &PowerLevel), With<Player>>,
+) {
+    let delta = time.delta().as_secs_f32();
+    let (lives, bombs, power) = match query.iter().next() {
+        Some((lives, bombs, power)) => (lives.0, bombs.0, power.0),
+        None => return,
+    };
+    rank.add((RANK_PER_SECOND + power as f32 * RANK_PER_POWER_SECOND) * delta);
+    rank.add(game_state.score.saturating_sub(last.score) as f32 * RANK_PER_POINT);
+    if lives < last.lives {
+        rank.add(-RANK_DEATH);
+    }
+    if bombs < last.bombs {
+        rank.add(-RANK_BOMB);
+    }
+    *last = RankTracking {
+        score: game_state.score,
+        lives,
+        bombs,
+    };
+}
+
+/** Scales enemies once they're spawned, runs after the commands of the frame were applied */
+fn enemy_difficulty_scale (
+    difficulty: Res<Difficulty>,
+    modifiers: Res<DailyModifiers>,
+    query: Query<(&mut Health, Option<&mut Gun>, Option<&mut GunCollection>, Option<&Boss>), Added<AI>>,
+) {
+    let settings = modifiers.settings(*difficulty);
+    query.for_each_mut(|(mut health, gun, gun_collection, boss)| {
+        health.0 *= settings.health;
+        health.1 *= settings.health;
+        if let Some(mut gun) = gun {
+            settings.scale_gun(&mut gun);
+        }
+        if let Some(mut gun_collection) = gun_collection {
+            // Boss patterns are already built for the settings
+            if boss.is_none() {
+                gun_collection.guns = gun_collection.guns.iter()
+                    .flat_map(|gun| settings.volley(gun, VOLLEY_SPACING))
+                    .collect();
+            }
+            for gun in gun_collection.guns.iter_mut() {
+                settings.scale_gun(gun);
+            }
+        }
+    });
+}
+
+pub struct DifficultyPlugin;
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .insert_resource(Difficulty::default())
+            .insert_resource(Rank::default())
+            .add_system(rank_update.system())
+            .add_system_to_stage(CoreStage::PostUpdate, enemy_difficulty_scale.system());
+    }
+}
@@ -1,29 +1,242 @@
 use std::f32::consts::PI;
 
-use bevy::{core::Time, math::{Vec2, Vec3}, prelude::{Bundle, Commands, Entity, IntoSystem, Plugin, Query, Res, SpriteSheetBundle, Transform, With}, sprite::TextureAtlasSprite};
+use bevy::{math::{Quat, Vec2, Vec3}, prelude::{Bundle, Commands, DespawnRecursiveExt, Entity, GlobalTransform, IntoSystem, Plugin, Query, Res, ResMut, SpriteSheetBundle, Transform, With, Without}, sprite::TextureAtlasSprite};
 
-use crate::{gun_systems::{Gun, GunCollection, GunCooldown}, laser_systems::{FromEnemy, LaserBundle}, util::{Health, HitBox, Materials, Speed}};
+use crate::{cleanup_systems::Despawnable, collision_systems::ContactDamage, difficulty_systems::Rank, game_systems::{GameState, GameTime}, gun_systems::{Gun, GunCollection, GunCooldown}, laser_systems::{Damage, FromEnemy, LaserBundle, LaserPool, insert_gun_modifiers}, part_systems::Part, player_systems::{Player, PlayerState}, util::{Health, HitBox, Materials, Rng, Speed, WinSize, facing_angle, facing_direction, facing_rotation}};
+
+/** Room for entrance paths swinging outside of the screen before enemies are culled */
+const ENEMY_MARGIN: f32 = 150.;
 
 //#region Components
 pub struct AI;
 
 pub struct AIState {
     pub movement: AIMoveStates,
-    pub state_step: f32
+    /** Seconds spent in the current state */
+    pub state_step: f32,
+    /** Seconds since spawning */
+    pub age: f32,
+    /** Direction locked in by movements that keep it for the whole state */
+    pub heading: Option<Vec2>,
+}
+impl AIState {
+    pub fn enter(&mut self, movement: AIMoveStates) {
+        self.movement = movement;
+        self.state_step = 0.;
+        self.heading = None;
+    }
 }
 impl Default for AIState {
     fn default() -> Self {
         Self { 
             movement: AIMoveStates::Entering, 
-            state_step: 0. 
+            state_step: 0.,
+            age: 0.,
+            heading: None,
         }
     }
 }
 
-#[derive(std::cmp::PartialEq)]
+#[derive(std::cmp::PartialEq, Clone, Copy)]
 pub enum AIMoveStates {
     Entering,
-    Hovering
+    Hovering,
+    Attacking,
+    /** Leaves the screen, despawned once outside of it */
+    Exiting,
+}
+
+/** Movement pattern used while in a state */
+#[derive(Clone, Copy)]
+pub enum AIMovement {
+    /** Chase the `AICircle` path picked by `AIEntrance` */
+    Circle,
+    /** Strafe with `AIHorizontal` */
+    Horizontal,
+    Hold,
+    /** Head for the player at this speed */
+    Dive(f32),
+    /** Lock on to where the player is and fly straight through at this speed */
+    Ram(f32),
+    /** Move down with the scrolling background */
+    Scroll,
+    /** Fly in a straight line at this velocity */
+    Straight(Vec2),
+    /** Follow the spline in `AIPath` */
+    Path,
+    /** Fly to the `FormationSlot` and stay in it */
+    Formation,
+}
+
+pub struct AIStateConfig {
+    /** Seconds before moving on to `next`, a `Path` also moves on once it ends */
+    pub duration: f32,
+    pub movement: AIMovement,
+    /** Whether guns and beams fire in this state */
+    pub guns: bool,
+    pub next: AIMoveStates,
+}
+
+/** What each state of the AI does and where it leads */
+pub struct AIBehavior {
+    pub entering: AIStateConfig,
+    pub hovering: AIStateConfig,
+    pub attacking: AIStateConfig,
+    pub exiting: AIStateConfig,
+    /** Seconds on screen before being forced to exit, whatever the state */
+    pub lifetime: Option<f32>,
+}
+impl AIBehavior {
+    pub fn config(&self, state: AIMoveStates) -> &AIStateConfig {
+        match state {
+            AIMoveStates::Entering => &self.entering,
+            AIMoveStates::Hovering => &self.hovering,
+            AIMoveStates::Attacking => &self.attacking,
+            AIMoveStates::Exiting => &self.exiting,
+        }
+    }
+    pub fn guns_active(&self, state: &AIState) -> bool {
+        self.config(state.movement).guns
+    }
+    /** Strafes in with `AIHorizontal`, then flies back up */
+    pub fn strafing() -> Self {
+        Self {
+            entering: AIStateConfig {
+                duration: 2.,
+                movement: AIMovement::Horizontal,
+                guns: false,
+                next: AIMoveStates::Hovering,
+            },
+            hovering: AIStateConfig {
+                duration: 8.,
+                movement: AIMovement::Horizontal,
+                guns: true,
+                next: AIMoveStates::Exiting,
+            },
+            exiting: AIStateConfig {
+                duration: 0.,
+                movement: AIMovement::Straight(Vec2::new(0., 250.)),
+                guns: false,
+                next: AIMoveStates::Exiting,
+            },
+            ..Default::default()
+        }
+    }
+    /** Flies along its `AIPath`, then keeps going down */
+    pub fn following_path() -> Self {
+        Self {
+            entering: AIStateConfig {
+                duration: 30.,
+                movement: AIMovement::Path,
+                guns: true,
+                next: AIMoveStates::Exiting,
+            },
+            lifetime: None,
+            ..Default::default()
+        }
+    }
+    /** Drops in, stops to lock on, then rams through where the player was and off the screen */
+    pub fn kamikaze() -> Self {
+        Self {
+            entering: AIStateConfig {
+                duration: 0.8,
+                movement: AIMovement::Straight(Vec2::new(0., -200.)),
+                guns: false,
+                next: AIMoveStates::Hovering,
+            },
+            hovering: AIStateConfig {
+                duration: 0.6,
+                movement: AIMovement::Hold,
+                guns: false,
+                next: AIMoveStates::Exiting,
+            },
+            exiting: AIStateConfig {
+                duration: 0.,
+                movement: AIMovement::Ram(450.),
+                guns: false,
+                next: AIMoveStates::Exiting,
+            },
+            lifetime: None,
+            ..Default::default()
+        }
+    }
+    /** Scrolls with the background for `duration` seconds, firing the whole way */
+    pub fn turret(duration: f32) -> Self {
+        let scroll = |next| AIStateConfig {
+            duration,
+            movement: AIMovement::Scroll,
+            guns: true,
+            next,
+        };
+        Self {
+            entering: scroll(AIMoveStates::Exiting),
+            exiting: scroll(AIMoveStates::Exiting),
+            lifetime: None,
+            ..Default::default()
+        }
+    }
+    /** Waits in its formation slot until sent diving, then flies back to it */
+    pub fn in_formation() -> Self {
+        Self {
+            entering: AIStateConfig {
+                duration: 3.,
+                movement: AIMovement::Formation,
+                guns: false,
+                next: AIMoveStates::Hovering,
+            },
+            hovering: AIStateConfig {
+                duration: f32::INFINITY,
+                movement: AIMovement::Formation,
+                guns: true,
+                next: AIMoveStates::Attacking,
+            },
+            attacking: AIStateConfig {
+                duration: 1.2,
+                movement: AIMovement::Dive(300.),
+                guns: false,
+                next: AIMoveStates::Hovering,
+            },
+            exiting: AIStateConfig {
+                duration: 0.,
+                movement: AIMovement::Straight(Vec2::new(0., 250.)),
+                guns: false,
+                next: AIMoveStates::Exiting,
+            },
+            lifetime: Some(30.),
+        }
+    }
+}
+impl Default for AIBehavior {
+    /** Circles in, holds its position, dives at the player and keeps going down */
+    fn default() -> Self {
+        Self {
+            entering: AIStateConfig {
+                duration: 5.,
+                movement: AIMovement::Circle,
+                guns: true,
+                next: AIMoveStates::Hovering,
+            },
+            hovering: AIStateConfig {
+                duration: 2.,
+                movement: AIMovement::Hold,
+                guns: true,
+                next: AIMoveStates::Attacking,
+            },
+            attacking: AIStateConfig {
+                duration: 1.5,
+                movement: AIMovement::Dive(250.),
+                guns: false,
+                next: AIMoveStates::Exiting,
+            },
+            exiting: AIStateConfig {
+                duration: 0.,
+                movement: AIMovement::Straight(Vec2::new(0., -350.)),
+                guns: false,
+                next: AIMoveStates::Exiting,
+            },
+            lifetime: Some(20.),
+        }
+    }
 }
 
 pub struct AICircle {
@@ -33,17 +246,42 @@ pub struct AICircle {
     pub y_radius: f32,
 }
 
-pub struct AIHorizontal;
+/** Strafes side to side around `x_origin`, after sliding down to `y_origin` */
+pub struct AIHorizontal {
+    pub x_origin: f32,
+    pub y_origin: f32,
+    pub amplitude: f32,
+    /** Radians per second */
+    pub frequency: f32,
+}
+
+/** Turns to face the player, in radians per second */
+pub struct Turret {
+    pub turn_rate: f32,
+}
 
 pub struct AIEntrance {
     pub direction: EntranceDirections
 }
 
 pub enum EntranceDirections {
+    /** Comes in from the right edge, circling counter-clockwise */
     Left,
+    /** Drops in from the top, tracing a figure eight */
     Up,
+    /** Comes in from the left edge, circling clockwise */
     Right,
 }
+impl EntranceDirections {
+    /** Off-screen position the enemy should be spawned at */
+    pub fn spawn_point(&self, win_size: &WinSize) -> Vec3 {
+        match self {
+            EntranceDirections::Left => Vec3::new(win_size.w + 100., win_size.h - 75., 10.),
+            EntranceDirections::Up => Vec3::new(win_size.half_w, win_size.h + 100., 10.),
+            EntranceDirections::Right => Vec3::new(-100., win_size.h - 75., 10.),
+        }
+    }
+}
 
 
 #[derive(Bundle)]
@@ -51,9 +289,12 @@ pub struct EnemyBundle {
     pub ai: AI,
     pub speed: Speed,
     pub state: AIState,
+    pub behavior: AIBehavior,
     pub weapon: GunCollection,
     pub health: Health,
     pub hitbox: HitBox,
+    pub contact: ContactDamage,
+    pub despawnable: Despawnable,
 
     #[bundle]
     pub sprite: SpriteSheetBundle
@@ -64,6 +305,7 @@ impl Default for EnemyBundle {
             ai: AI,
             speed: Speed(10., 10.),
             state: Default::default(),
+            behavior: Default::default(),
             weapon: GunCollection {
                 guns: Box::new([
                     Gun {
@@ -78,6 +320,8 @@ impl Default for EnemyBundle {
             hitbox: HitBox {
                 rect: Vec3::new(50., 50., 1.)
             },
+            contact: ContactDamage(20.),
+            despawnable: Despawnable::after_entering(ENEMY_MARGIN),
             sprite: SpriteSheetBundle {
                 transform: Transform {
                     scale: Vec3::new(2.,2., 1.),
@@ -92,34 +336,65 @@ impl Default for EnemyBundle {
 //#endregion
 
 fn enemy_update (
-    time: Res<Time>,
-    query: Query<(Entity, &mut AIState, With<AI>)>
+    time: Res<GameTime>,
+    query: Query<(&mut AIState, &AIBehavior, With<AI>)>
 ) {
-    query.for_each_mut(|(entity, mut state, _)| {
-        state.state_step += time.delta().as_secs_f32();
+    query.for_each_mut(|(mut state, behavior, _)| {
+        let delta = time.delta().as_secs_f32();
+        state.state_step += delta;
+        state.age += delta;
+
+        // Leaves for good, its Despawnable culls it once off-screen
+        if state.movement == AIMoveStates::Exiting {
+            return;
+        }
+
+        let config = behavior.config(state.movement);
+        let expired = behavior.lifetime.map_or(false, |lifetime| state.age >= lifetime);
+        let next = if expired {
+            AIMoveStates::Exiting
+        } else if state.state_step >= config.duration {
+            config.next
+        } else {
+            return;
+        };
+        state.enter(next);
     });
 }
 
 fn enemy_entrance_circle_movement (
-    query: Query<(&mut Transform, &AIState, &AIEntrance, &AICircle, &Speed, With<AI>)>
+    query: Query<(&mut Transform, &AIState, &AIBehavior, &AIEntrance, &AICircle, &Speed, With<AI>)>
 ) {
     query.for_each_mut(|(
             mut transform, 
             state, 
+            behavior,
             entrance, 
             circle, 
             speed, _
         )| {
-            if state.movement == AIMoveStates::Entering {
+            if let AIMovement::Circle = behavior.config(state.movement).movement {
                 let max_distance_x = state.state_step * speed.0;
                 let max_distance_y = state.state_step * speed.1;
                 // Compute angles
                 let tx = speed.0 * state.state_step % 360. / PI;
                 let ty = speed.1 * state.state_step % 360. / PI;
 
-                // Calculate circle position?
-                let x_dst = circle.x_radius * tx.cos() + circle.x_origin;
-                let y_dst = circle.y_radius * ty.sin() + circle.y_origin;
+                // Point on the path the enemy is chasing
+                let (x_dst, y_dst) = match entrance.direction {
+                    EntranceDirections::Left => (
+                        circle.x_radius * tx.cos() + circle.x_origin,
+                        circle.y_radius * ty.sin() + circle.y_origin,
+                    ),
+                    EntranceDirections::Up => (
+                        circle.x_radius * tx.sin() + circle.x_origin,
+                        circle.y_radius * (2. * ty).sin() / 2. + circle.y_origin,
+                    ),
+                    EntranceDirections::Right => (
+                        circle.x_origin - circle.x_radius * tx.cos(),
+                        circle.y_radius * ty.sin() + circle.y_origin,
+                    ),
+                };
 
                 let current_x = transform.translation.x;
                 let current_y = transform.translation.y;
@@ -148,57 +423,149 @@ fn enemy_entrance_circle_movement (
                 // Apply maths
                 transform.translation.x = x;
                 transform.translation.y = y;
-
-                //match entrance.direction {
-                //    EntranceDirections::Left => {
-                //    },
-                //    EntranceDirections::Up => {},
-                //    EntranceDirections::Right => {},
-                //}
             }
     });
 }
 
+fn enemy_horizontal_movement (
+    time: Res<GameTime>,
+    query: Query<(&mut Transform, &AIState, &AIBehavior, &AIHorizontal, &Speed), With<AI>>
+) {
+    let delta = time.delta().as_secs_f32();
+    query.for_each_mut(|(mut transform, state, behavior, horizontal, speed)| {
+        if !matches!(behavior.config(state.movement).movement, AIMovement::Horizontal) {
+            return;
+        }
+        let dy = horizontal.y_origin - transform.translation.y;
+        let step_y = speed.1.abs() * delta;
+        transform.translation.y += dy.clamp(-step_y, step_y);
+        // Eased so switching into the pattern mid-screen doesn't jump
+        let x = horizontal.x_origin
+            + horizontal.amplitude * (state.age * horizontal.frequency).sin();
+        transform.translation.x += (x - transform.translation.x) * (8. * delta).min(1.);
+    });
+}
+
+fn enemy_pattern_movement (
+    time: Res<GameTime>,
+    game_state: Res<GameState>,
+    player_query: Query<&Transform, With<Player>>,
+    query: Query<(&mut Transform, &mut AIState, &AIBehavior), (With<AI>, Without<Player>)>
+) {
+    let delta = time.delta().as_secs_f32();
+    let player = player_query.iter().next().map(|transform| transform.translation);
+    query.for_each_mut(|(mut transform, mut state, behavior)| {
+        match behavior.config(state.movement).movement {
+            AIMovement::Dive(speed) => {
+                // Keep going the same way once the player is gone
+                let direction = player
+                    .map(|player| (player - transform.translation).truncate().normalize_or_zero())
+                    .filter(|direction| *direction != Vec2::ZERO)
+                    .unwrap_or(Vec2::new(0., -1.));
+                transform.translation += (direction * speed * delta).extend(0.);
+            },
+            AIMovement::Ram(speed) => {
+                let translation = transform.translation;
+                let heading = *state.heading.get_or_insert_with(|| {
+                    player
+                        .map(|player| (player - translation).truncate().normalize_or_zero())
+                        .filter(|direction| *direction != Vec2::ZERO)
+                        .unwrap_or(Vec2::new(0., -1.))
+                });
+                transform.translation += (heading * speed * delta).extend(0.);
+                transform.rotation = facing_rotation(heading);
+            },
+            AIMovement::Scroll => {
+                transform.translation.y -= game_state.scroll;
+            },
+            AIMovement::Straight(velocity) => {
+                transform.translation += (velocity * delta).extend(0.);
+            },
+            _ => {},
+        }
+    });
+}
+
+fn enemy_turret_aim (
+    time: Res<GameTime>,
+    player_query: Query<&Transform, With<Player>>,
+    query: Query<(&mut Transform, &Turret), (With<AI>, Without<Player>)>
+) {
+    let player = match player_query.iter().next() {
+        Some(transform) => transform.translation,
+        None => return,
+    };
+    let delta = time.delta().as_secs_f32();
+    query.for_each_mut(|(mut transform, turret)| {
+        let to_player = (player - transform.translation).truncate();
+        let wanted = facing_angle(to_player);
+        let current = facing_angle(facing_direction(transform.rotation));
+        let mut turn = wanted - current;
+        if turn > PI {
+            turn -= 2. * PI;
+        } else if turn < -PI {
+            turn += 2. * PI;
+        }
+        let max_turn = turret.turn_rate * delta;
+        transform.rotation = Quat::from_rotation_z(current + turn.clamp(-max_turn, max_turn));
+    });
+}
+
 fn enemy_shoot (
     mut commands: Commands,
+    mut pool: ResMut<LaserPool>,
+    rank: Res<Rank>,
     materials: Res<Materials>,
-    query: Query<(Entity, &Transform, &mut Gun, With<AI>)>,
-    query2: Query<(Entity, &Transform, &mut GunCollection, With<AI>)>
+    mut rng: ResMut<Rng>,
+    player_query: Query<(&Transform, &PlayerState), With<Player>>,
+    query: Query<(Entity, &Transform, &mut Gun, &AIState, &AIBehavior, With<AI>)>,
+    query2: Query<(Entity, &Transform, &mut GunCollection, &AIState, &AIBehavior, With<AI>)>,
+    part_query: Query<(&GlobalTransform, &mut GunCollection, &Part), (With<AI>, Without<AIState>)>,
+    owner_query: Query<(&AIState, &AIBehavior)>,
 ) {
+    let target = player_query.iter().next()
+        .map(|(transform, state)| (transform.translation.truncate(), state.velocity));
     let mut shoot_guns = 
         |transform: &Transform, gun: &mut Gun| {
+            let aimed = gun.aimed_speed(transform, target, &mut rng.aim);
+            let speed = Speed(aimed.0 * rank.bullet_speed(), aimed.1 * rank.bullet_speed());
             let x = transform.translation.x;
             let y = transform.translation.y;
             let off_x = gun.offset.x;
             let off_y = gun.offset.y;
-            commands
-                .spawn_bundle(LaserBundle {
-                    sprite: SpriteSheetBundle {
-                        texture_atlas: materials.projectile_atlas.clone(),
-                        sprite: TextureAtlasSprite {
-                            index: 2,
-                            ..Default::default() 
-                        },
-                        transform: Transform {
-                            translation: Vec3::new(x + off_x, y + off_y, 0.),
-                            scale: Vec3::new(2., 2., 1.),
-                            ..Default::default()
-                        },
-                        ..Default::default()
+            let mut laser = pool.spawn(&mut commands, LaserBundle {
+                sprite: SpriteSheetBundle {
+                    texture_atlas: materials.projectile_atlas.clone(),
+                    sprite: TextureAtlasSprite {
+                        index: 2,
+                        ..Default::default() 
+                    },
+                    transform: Transform {
+                        translation: Vec3::new(x + off_x, y + off_y, 0.),
+                        rotation: facing_rotation(Vec2::new(speed.0, speed.1)),
+                        scale: Vec3::new(2., 2., 1.),
                     },
-                    speed: gun.initial_speed.clone(),
                     ..Default::default()
-                }).insert(FromEnemy);
-            gun.cooldown.0 = gun.cooldown.1;
+                },
+                speed,
+                damage: Damage(gun.damage, gun.damage),
+                ..Default::default()
+            });
+            laser.insert(FromEnemy);
+            insert_gun_modifiers(&mut laser, gun);
+            gun.cooldown.0 = gun.cooldown.1 / rank.fire_rate();
         };
         
-    query.for_each_mut(|(entity, transform, mut gun, _)| {
-        if gun.cooldown.0 == 0. {
+    query.for_each_mut(|(entity, transform, mut gun, state, behavior, _)| {
+        if gun.cooldown.0 == 0. && behavior.guns_active(state) {
             shoot_guns(&transform, &mut gun);
         }
     });
 
-    query2.for_each_mut(|(entity, transform, mut gun_collection, _)| {
+    query2.for_each_mut(|(entity, transform, mut gun_collection, state, behavior, _)| {
+        if !behavior.guns_active(state) {
+            return;
+        }
         let mut guns = &mut *gun_collection.guns;
         for gun in guns {
             if gun.cooldown.0 == 0. {
@@ -206,6 +573,20 @@ fn enemy_shoot (
             } 
         }
     });
+
+    // Parts fire while their owner's guns are active
+    part_query.for_each_mut(|(global, mut gun_collection, part)| {
+        match owner_query.get(part.owner) {
+            Ok((state, behavior)) if behavior.guns_active(state) => {},
+            _ => return,
+        }
+        let transform = Transform::from(*global);
+        for gun in gun_collection.guns.iter_mut() {
+            if gun.cooldown.0 == 0. {
+                shoot_guns(&transform, gun);
+            }
+        }
+    });
 }
 
 pub struct EnemyPlugin;
@@ -214,6 +595,9 @@ impl Plugin for EnemyPlugin {
         app
             .add_system(enemy_shoot.system())
             .add_system(enemy_update.system())
-            .add_system(enemy_entrance_circle_movement.system());
+            .add_system(enemy_entrance_circle_movement.system())
+            .add_system(enemy_horizontal_movement.system())
+            .add_system(enemy_pattern_movement.system())
+            .add_system(enemy_turret_aim.system());
     }
 }
\ No newline at end of file
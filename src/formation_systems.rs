@@ -0,0 +1,235 @@
+use bevy::{math::{Vec2, Vec3}, prelude::{Commands, Entity, EventReader, IntoSystem, Plugin, Query, Res, ResMut, SpriteSheetBundle, Transform, With}, sprite::TextureAtlasSprite};
+
+use crate::{enemy_systems::{AI, AIBehavior, AIMoveStates, AIMovement, AIState, EnemyBundle}, game_systems::{GameState, GameTime}, gun_systems::{Gun, GunAim, GunCollection, GunCooldown}, health_systems::EnemyDestroyed, pickup_systems::{PickupKind, PickupMaterials, spawn_pickup}, util::{Materials, Speed, WinSize}};
+
+/** How fast members fly back to their slot, units per second */
+const SLOT_SPEED: f32 = 300.;
+/** Score per member for wiping out a whole formation */
+const FORMATION_BONUS: u32 = 200;
+
+pub enum FormationLayout {
+    Grid {
+        columns: usize,
+        rows: usize,
+        spacing: Vec2,
+    },
+    /** Pointing down, `count` members including the tip */
+    V {
+        count: usize,
+        spacing: Vec2,
+    },
+}
+impl FormationLayout {
+    /** Offsets of each slot from the formation's center */
+    pub fn slots(&self) -> Vec<Vec2> {
+        match self {
+            FormationLayout::Grid { columns, rows, spacing } => {
+                let half = Vec2::new((*columns as f32 - 1.) / 2., (*rows as f32 - 1.) / 2.);
+                (0..*rows)
+                    .flat_map(|row| (0..*columns).map(move |column| (column, row)))
+                    .map(|(column, row)| (Vec2::new(column as f32, row as f32) - half) * *spacing)
+                    .collect()
+            },
+            FormationLayout::V { count, spacing } => {
+                (0..*count)
+                    .map(|i| {
+                        let arm = ((i + 1) / 2) as f32;
+                        let side = if i % 2 == 0 { 1. } else { -1. };
+                        Vec2::new(side * arm * spacing.x, arm * spacing.y)
+                    })
+                    .collect()
+            },
+        }
+    }
+}
+
+//#region Components
+/** Group moving as a unit, members fly to their slot around its center */
+pub struct Formation {
+    pub origin: Vec2,
+    pub sway_amplitude: f32,
+    /** Radians per second */
+    pub sway_speed: f32,
+    /** How much the slots spread out and in, as a ratio of their offset */
+    pub breathe: f32,
+    /** Seconds between members diving at the player */
+    pub dive_interval: f32,
+    pub members: Vec<Entity>,
+    pub total: usize,
+    pub destroyed: usize,
+    pub center: Vec2,
+    pub scale: f32,
+    age: f32,
+    next_dive: f32,
+    dives: usize,
+}
+impl Formation {
+    pub fn new(origin: Vec2) -> Self {
+        Self {
+            origin,
+            sway_amplitude: 60.,
+            sway_speed: 0.8,
+            breathe: 0.15,
+            dive_interval: 2.5,
+            members: Vec::new(),
+            total: 0,
+            destroyed: 0,
+            center: origin,
+            scale: 1.,
+            age: 0.,
+            next_dive: 0.,
+            dives: 0,
+        }
+    }
+}
+
+pub struct FormationSlot {
+    pub formation: Entity,
+    pub offset: Vec2,
+}
+//#endregion
+
+/** Spawns the formation above the screen, members fly down into their slots */
+pub fn spawn_formation (
+    commands: &mut Commands,
+    assets: &Materials,
+    win_size: &WinSize,
+    layout: FormationLayout,
+    mut formation: Formation,
+) {
+    let formation_entity = commands.spawn().id();
+    for offset in layout.slots() {
+        let position = formation.origin + offset + Vec2::new(0., win_size.h);
+        let member = commands
+            .spawn_bundle(EnemyBundle {
+                sprite: SpriteSheetBundle {
+                    texture_atlas: assets.enemy_atlas.clone(),
+                    sprite: TextureAtlasSprite {
+                        index: 0,
+                        ..Default::default()
+                    },
+                    transform: Transform {
+                        translation: Vec3::new(position.x, position.y, 10.),
+                        scale: Vec3::new(3., 3., 1.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                behavior: AIBehavior::in_formation(),
+                weapon: GunCollection {
+                    guns: Box::new([
+                        Gun {
+                            cooldown: GunCooldown(1., 3.),
+                            initial_speed: Speed(0., -250.),
+                            aim: GunAim::AtPlayer,
+                            inaccuracy: 0.3,
+                            ..Default::default()
+                        }
+                    ])
+                },
+                ..Default::default()
+            })
+            .insert(FormationSlot {
+                formation: formation_entity,
+                offset,
+            })
+            .id();
+        formation.members.push(member);
+    }
+    formation.total = formation.members.len();
+    commands.entity(formation_entity).insert(formation);
+}
+
+fn formation_update (
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut game_state: ResMut<GameState>,
+    pickup_materials: Res<PickupMaterials>,
+    mut destroyed: EventReader<EnemyDestroyed>,
+    mut formation_query: Query<(Entity, &mut Formation)>,
+    mut member_query: Query<&mut AIState, With<FormationSlot>>,
+) {
+    let delta = time.delta().as_secs_f32();
+    for event in destroyed.iter() {
+        formation_query.for_each_mut(|(_, mut formation)| {
+            if let Some(index) = formation.members.iter().position(|member| *member == event.entity) {
+                formation.members.remove(index);
+                formation.destroyed += 1;
+                if formation.destroyed == formation.total {
+                    game_state.score += FORMATION_BONUS * formation.total as u32;
+                    spawn_pickup(&mut commands, &pickup_materials, PickupKind::Power, event.translation);
+                }
+            }
+        });
+    }
+
+    formation_query.for_each_mut(|(entity, mut formation)| {
+        // Members that flew away don't count towards the bonus anymore
+        let members: Vec<Entity> = formation.members.iter()
+            .cloned()
+            .filter(|member| member_query.get_mut(*member).is_ok())
+            .collect();
+        formation.members = members;
+        if formation.members.is_empty() {
+            commands.entity(entity).despawn();
+            return;
+        }
+
+        formation.age += delta;
+        let age = formation.age;
+        formation.center = formation.origin + Vec2::new(formation.sway_amplitude * (age * formation.sway_speed).sin(), 0.);
+        formation.scale = 1. + formation.breathe * (age * 2.).sin();
+
+        // Send the next member waiting in its slot after the player
+        formation.next_dive -= delta;
+        if formation.next_dive > 0. {
+            return;
+        }
+        formation.next_dive = formation.dive_interval;
+        for i in 0..formation.members.len() {
+            let member = formation.members[(formation.dives + i) % formation.members.len()];
+            if let Ok(mut state) = member_query.get_mut(member) {
+                if state.movement == AIMoveStates::Hovering {
+                    state.enter(AIMoveStates::Attacking);
+                    formation.dives += 1;
+                    break;
+                }
+            }
+        }
+    });
+}
+
+fn formation_member_movement (
+    time: Res<GameTime>,
+    formation_query: Query<&Formation>,
+    query: Query<(&mut Transform, &FormationSlot, &AIState, &AIBehavior), With<AI>>,
+) {
+    let delta = time.delta().as_secs_f32();
+    query.for_each_mut(|(mut transform, slot, state, behavior)| {
+        if !matches!(behavior.config(state.movement).movement, AIMovement::Formation) {
+            return;
+        }
+        let formation = match formation_query.get(slot.formation) {
+            Ok(formation) => formation,
+            Err(_) => return,
+        };
+        let target = formation.center + slot.offset * formation.scale;
+        let to_target = target - transform.translation.truncate();
+        let step = SLOT_SPEED * delta;
+        let movement = if to_target.length() > step {
+            to_target.normalize() * step
+        } else {
+            to_target
+        };
+        transform.translation += movement.extend(0.);
+    });
+}
+
+pub struct FormationPlugin;
+impl Plugin for FormationPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .add_system(formation_update.system())
+            .add_system(formation_member_movement.system());
+    }
+}
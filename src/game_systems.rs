@@ -1,20 +1,143 @@
-use bevy::{core::Time, math::Vec3, prelude::{Commands, HorizontalAlign, IntoSystem, Plugin, Query, Res, ResMut, SystemStage, Transform, VerticalAlign, With}, text::{Text, Text2dBundle, TextAlignment, TextSection, TextStyle}};
+use std::{env, time::Duration};
 
-use crate::util::{Materials, WinSize};
+use bevy::{core::Time, ecs::schedule::ShouldRun, math::Vec3, prelude::{Commands, CoreStage, Entity, HorizontalAlign, IntoSystem, ParallelSystemDescriptorCoercion, Plugin, Query, Res, ResMut, SystemStage, Transform, VerticalAlign, With, Without}, text::{Text, Text2dBundle, TextAlignment, TextSection, TextStyle}};
+
+use crate::{stage_systems::STAGES, util::{Materials, Rng, WinSize}};
+
+pub const METERS_PER_SECOND: f32 = 10.;
+/** Pixels the background scrolls for each meter travelled */
+pub const PIXELS_PER_METER: f32 = 8.;
+/** Stage running the menus, before the gameplay systems which only run while playing */
+pub const MENU_STAGE: &str = "menu";
+/** Stage moving the game time, once the menus may have started playing */
+pub const CLOCK_STAGE: &str = "clock";
+/** Label of the system moving the game time */
+pub const GAME_CLOCK: &str = "game_clock";
+
+/** What's on screen, gameplay is frozen outside of `Playing` */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Screen {
+    Title,
+    Playing,
+    /** Between stages, score and bonuses of the stage just cleared */
+    Results,
+    /** After the last stage */
+    Ending,
+}
+
+/** Picked on the title screen */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameMode {
+    /** The authored stages one after the other, up to the ending */
+    Stages,
+    /** Generated waves getting harder with the distance, until out of lives */
+    Endless,
+    /** Endless with the seed and modifiers of the day */
+    Daily,
+    /** A stage from any point, with no high score */
+    Practice,
+    /** The boss of every stage one after the other, against the clock */
+    BossRush,
+    /** Endless for that many minutes */
+    ScoreAttack(u32),
+}
+impl GameMode {
+    pub const ALL: [GameMode; 7] = [
+        GameMode::Stages,
+        GameMode::Endless,
+        GameMode::Daily,
+        GameMode::Practice,
+        GameMode::BossRush,
+        GameMode::ScoreAttack(2),
+        GameMode::ScoreAttack(5),
+    ];
+
+    /** Shown on the title screen, also names the high score tables */
+    pub fn name(&self) -> String {
+        match self {
+            GameMode::Stages => "Stages".to_owned(),
+            GameMode::Endless => "Endless".to_owned(),
+            GameMode::Daily => "Daily".to_owned(),
+            GameMode::Practice => "Practice".to_owned(),
+            GameMode::BossRush => "Boss-Rush".to_owned(),
+            GameMode::ScoreAttack(minutes) => format!("Attack-{}min", minutes),
+        }
+    }
+
+    /** Generated waves with no boss at the end */
+    pub fn endless(&self) -> bool {
+        matches!(self, GameMode::Endless | GameMode::Daily | GameMode::ScoreAttack(_))
+    }
+
+    /** Seconds before the run ends on its own */
+    pub fn time_limit(&self) -> Option<f32> {
+        match self {
+            GameMode::ScoreAttack(minutes) => Some(*minutes as f32 * 60.),
+            _ => None,
+        }
+    }
+}
+
+/** Time as the gameplay sees it, only moving while playing so a replay can set every frame */
+#[derive(Default)]
+pub struct GameTime {
+    delta: Duration,
+    elapsed: Duration,
+    /** Length of the next frame instead of the real one, set by a replay */
+    pub next_delta: Option<Duration>,
+}
+impl GameTime {
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /** Seconds played since the run started */
+    pub fn elapsed_seconds(&self) -> f64 {
+        self.elapsed.as_secs_f64()
+    }
+}
+
+/** Sent when leaving the title screen, for the modes to set the run up */
+pub struct RunStarted;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum StageStatus {
+    Running,
+    /** Distance reached, fighting the boss */
+    Boss(Entity),
+    Cleared,
+    /** Out of lives */
+    GameOver,
+    /** Out of time in score attack */
+    TimeUp,
+}
 
 pub struct GameState {
     pub distance: Distance,
+    pub score: u32,
+    pub status: StageStatus,
+    /** Pixels the background scrolled during the last frame */
+    pub scroll: f32,
 }
 
 pub struct Distance (pub f32, pub f32);
 
 pub struct DistanceText;
 
+pub struct ScoreText;
+
 fn setup_gamestate (
     mut commands: Commands,
 ) {
     commands.insert_resource(GameState {
-        distance: Distance(0., 1000.),
+        distance: Distance(0., STAGES[0].distance),
+        score: 0,
+        status: StageStatus::Running,
+        scroll: 0.,
     });
 }
 
@@ -63,31 +186,86 @@ fn spawn_ui (
             ..Default::default()
         })
         .insert(DistanceText);
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "0",
+                TextStyle {
+                    font: assets.font.clone(),
+                    font_size: 28.,
+                    ..Default::default()
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Bottom,
+                    horizontal: HorizontalAlign::Right,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(15., win_size.h - 15., 69.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(ScoreText);
+}
+
+fn game_clock (
+    time: Res<Time>,
+    mut game_time: ResMut<GameTime>,
+) {
+    let delta = game_time.next_delta.take().unwrap_or_else(|| time.delta());
+    game_time.delta = delta;
+    game_time.elapsed += delta;
 }
 
 fn update_distance(
-    time: Res<Time>, 
+    time: Res<GameTime>, 
     mut game_state: ResMut<GameState>
 ) {
     let mut d = &mut game_state.distance;
+    let previous = d.0;
     if d.0 < d.1 {
-        d.0 += time.delta().as_secs_f32() * 10.;
+        d.0 += time.delta().as_secs_f32() * METERS_PER_SECOND;
         if d.0 > d.1 {
             d.0 = d.1;
         }
     }
+    game_state.scroll = (game_state.distance.0 - previous) * PIXELS_PER_METER;
 }
 
 fn update_ui (
     mut game_state: ResMut<GameState>,
     query: Query<(&mut Text, With<DistanceText>)>,
+    score_query: Query<(&mut Text, With<ScoreText>), Without<DistanceText>>,
 ) {
     query.for_each_mut(
         |(mut text, _)| {
             text.sections.get_mut(0).unwrap().value = 
                 game_state.distance.0.round().to_string();
+            // The goal changes from one stage to the next, endless runs have none
+            text.sections.get_mut(1).unwrap().value = if game_state.distance.1.is_finite() {
+                ["/".to_owned(), game_state.distance.1.round().to_string(), "m".to_owned()].concat()
+            } else {
+                "m".to_owned()
+            };
         }
     );
+    score_query.for_each_mut(
+        |(mut text, _)| {
+            text.sections.get_mut(0).unwrap().value = 
+                game_state.score.to_string();
+        }
+    );
+}
+
+/** Run criteria for the gameplay systems */
+fn playing (
+    screen: Res<Screen>,
+) -> ShouldRun {
+    match *screen {
+        Screen::Playing => ShouldRun::Yes,
+        _ => ShouldRun::No,
+    }
 }
 
 pub struct GameSystemsPlugin;
@@ -95,6 +273,16 @@ pub struct GameSystemsPlugin;
 impl Plugin for GameSystemsPlugin {
     fn build(&self, app: &mut bevy::prelude::AppBuilder) {
         app
+            .insert_resource(Screen::Title)
+            .insert_resource(GameMode::Stages)
+            .add_event::<RunStarted>()
+            // New seed every run, shown on the results screen to play it again with --seed
+            .insert_resource(Rng::from_args(env::args()).unwrap_or_else(Rng::from_clock))
+            .insert_resource(GameTime::default())
+            .add_stage_before(CoreStage::Update, MENU_STAGE, SystemStage::parallel())
+            .add_stage_after(MENU_STAGE, CLOCK_STAGE, SystemStage::parallel().with_run_criteria(playing.system()))
+            .add_system_to_stage(CLOCK_STAGE, game_clock.system().label(GAME_CLOCK))
+            .stage(CoreStage::Update, |stage: &mut SystemStage| stage.set_run_criteria(playing.system()))
             .add_startup_system(setup_gamestate.system())
             .add_startup_stage("game_setup_ui", SystemStage::single(spawn_ui.system()))
             .add_system(update_distance.system())
@@ -1,8 +1,9 @@
-use bevy::{core::Time, math::Vec3, prelude::{IntoSystem, Plugin, Query, Res}};
+use bevy::{math::{Vec2, Vec3}, prelude::{IntoSystem, Plugin, Query, Res, Transform}};
 
-use crate::util::Speed;
+use crate::{game_systems::GameTime, modifier_systems::{Explosion, Split}, util::{Random, Speed, facing_direction}};
 
 /** First is current cooldown, second is reset cooldown */
+#[derive(Clone)]
 pub struct GunCooldown(pub f32, pub f32);
 impl Default for GunCooldown {
     fn default() -> Self {
@@ -10,11 +11,92 @@ impl Default for GunCooldown {
     }
 }
 
+#[derive(Clone, Copy)]
+pub enum GunAim {
+    /** Always fires at `initial_speed` */
+    Fixed,
+    /** At the player's current position */
+    AtPlayer,
+    /** Where the player will be if they keep moving the same way */
+    Lead,
+    /** Radians off the way the shooter is facing */
+    Facing(f32),
+}
+
+#[derive(Clone)]
 pub struct Gun {
     pub damage: f32,
     pub cooldown: GunCooldown,
     pub offset: Vec3,
     pub initial_speed: Speed,
+    /** Turn rate in radians per second of homing lasers, `None` flies straight */
+    pub homing: Option<f32>,
+    /** Seconds before the laser disappears on its own */
+    pub lifetime: Option<f32>,
+    /** Targets the laser goes through before it's used up */
+    pub pierce: Option<u32>,
+    /** Times the laser bounces off the screen edges */
+    pub bounce: Option<u32>,
+    /** Fragments the laser breaks into */
+    pub split: Option<Split>,
+    /** Area damage when the laser is used up */
+    pub explosion: Option<Explosion>,
+    /** Aimed guns keep the length of `initial_speed` as their speed */
+    pub aim: GunAim,
+    /** Width in radians of the cone shots randomly spread over */
+    pub inaccuracy: f32,
+}
+impl Gun {
+    /** Speed of a laser fired from `from`, `target` is the position and velocity to aim for */
+    pub fn aimed_speed(&self, from: &Transform, target: Option<(Vec2, Vec2)>, random: &mut Random) -> Speed {
+        let base = Vec2::new(self.initial_speed.0, self.initial_speed.1);
+        let speed = base.length();
+        let origin = (from.translation + self.offset).truncate();
+        let direction = match (self.aim, target) {
+            (GunAim::AtPlayer, Some((position, _))) => position - origin,
+            (GunAim::Lead, Some((position, velocity))) => {
+                intercept(origin, speed, position, velocity).unwrap_or(position) - origin
+            },
+            (GunAim::Facing(angle), _) => {
+                let facing = facing_direction(from.rotation);
+                let heading = facing.y.atan2(facing.x) + angle;
+                Vec2::new(heading.cos(), heading.sin())
+            },
+            _ => base,
+        };
+        let heading = direction.y.atan2(direction.x)
+            + random.range(-self.inaccuracy / 2., self.inaccuracy / 2.);
+        Speed(heading.cos() * speed, heading.sin() * speed)
+    }
+}
+
+/** Where a shot at `speed` from `origin` meets a target moving at `velocity`, if it can */
+fn intercept(origin: Vec2, speed: f32, position: Vec2, velocity: Vec2) -> Option<Vec2> {
+    let to_target = position - origin;
+    let a = velocity.length_squared() - speed * speed;
+    let b = 2. * to_target.dot(velocity);
+    let c = to_target.length_squared();
+    let time = if a.abs() < f32::EPSILON {
+        -c / b
+    } else {
+        let discriminant = b * b - 4. * a * c;
+        if discriminant < 0. {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        let (t1, t2) = ((-b - root) / (2. * a), (-b + root) / (2. * a));
+        match (t1 > 0., t2 > 0.) {
+            (true, true) => t1.min(t2),
+            (true, false) => t1,
+            (false, true) => t2,
+            _ => return None,
+        }
+    };
+    if time.is_finite() && time > 0. {
+        Some(position + velocity * time)
+    } else {
+        None
+    }
 }
 impl Default for Gun {
     fn default() -> Self {
@@ -22,7 +104,15 @@ impl Default for Gun {
             damage: 1.,
             cooldown: GunCooldown::default(),
             offset: Vec3::ZERO,
-            initial_speed: Speed(0., 500.)
+            initial_speed: Speed(0., 500.),
+            homing: None,
+            lifetime: None,
+            pierce: None,
+            bounce: None,
+            split: None,
+            explosion: None,
+            aim: GunAim::Fixed,
+            inaccuracy: 0.,
         }
     }
 }
@@ -40,7 +130,7 @@ impl Default for GunCollection {
 
 //#region Gun Systems
 fn gun_cooldown(
-    time: Res<Time>,
+    time: Res<GameTime>,
     mut query: Query<&mut Gun>,
     mut query2: Query<&mut GunCollection>
 ) {
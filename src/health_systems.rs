@@ -1,14 +1,54 @@
-use bevy::prelude::{Commands, Entity, IntoSystem, Plugin, Query};
+use bevy::{core::Timer, math::Vec3, prelude::{Commands, DespawnRecursiveExt, Entity, EventWriter, IntoSystem, Plugin, Query, Res, Transform, Without}, sprite::TextureAtlasSprite};
 
-use crate::util::Health;
+use crate::{enemy_systems::AI, game_systems::GameTime, player_systems::Lives, util::Health};
+
+/** Entity can't take damage until the timer runs out */
+pub struct Invulnerable(pub Timer);
+impl Invulnerable {
+    pub fn from_seconds(seconds: f32) -> Self {
+        Self (Timer::from_seconds(seconds, false))
+    }
+}
+
+/** Sent when an enemy runs out of health, before it is despawned */
+pub struct EnemyDestroyed {
+    pub entity: Entity,
+    pub translation: Vec3,
+}
 
 fn health_update(
     mut commands: Commands,
-    query: Query<(Entity, &Health)>
+    mut destroyed: EventWriter<EnemyDestroyed>,
+    query: Query<(Entity, &Health, &Transform, Option<&AI>), Without<Lives>>
 ) {
-    query.for_each_mut(|(entity, health)| {
+    query.for_each_mut(|(entity, health, transform, ai)| {
         if health.0 <= 0. {
-            commands.entity(entity).despawn();
+            if ai.is_some() {
+                destroyed.send(EnemyDestroyed {
+                    entity,
+                    translation: transform.translation,
+                });
+            }
+            commands.entity(entity).despawn_recursive();
+        }
+    });
+}
+
+fn invulnerable_update(
+    mut commands: Commands,
+    time: Res<GameTime>,
+    query: Query<(Entity, &mut Invulnerable, Option<&mut TextureAtlasSprite>)>
+) {
+    query.for_each_mut(|(entity, mut invulnerable, sprite)| {
+        invulnerable.0.tick(time.delta());
+        let finished = invulnerable.0.finished();
+        // Blink the sprite while invulnerable
+        if let Some(mut sprite) = sprite {
+            let blink = (invulnerable.0.elapsed_secs() * 10.) as u32 % 2 == 0;
+            sprite.color.set_a(if blink || finished { 1. } else { 0.3 });
+        }
+        if finished {
+            commands.entity(entity).remove::<Invulnerable>();
         }
     });
 }
@@ -17,6 +57,8 @@ pub struct HealthPlugin;
 impl Plugin for HealthPlugin {
     fn build(&self, app: &mut bevy::prelude::AppBuilder) {
         app
-            .add_system(health_update.system());
+            .add_event::<EnemyDestroyed>()
+            .add_system(health_update.system())
+            .add_system(invulnerable_update.system());
     }
-}
\ No newline at end of file
+}
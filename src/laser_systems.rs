@@ -1,6 +1,13 @@
-use bevy::{ecs::bundle::Bundle, math::Vec2, prelude::{Commands, Entity, IntoSystem, Plugin, Query, Res, SpriteSheetBundle, Transform, With}};
+use std::f32::consts::PI;
 
-use crate::{enemy_systems::AI, util::{Health, HitBox, Speed, TIME_STEP, WinSize}};
+use bevy::{ecs::{bundle::Bundle, system::EntityCommands}, math::{Quat, Vec2}, prelude::{Commands, CoreStage, Entity, EventWriter, GlobalTransform, IntoSystem, Plugin, Query, Res, ResMut, SpriteSheetBundle, Transform, With, Without}};
+
+use crate::{cleanup_systems::Despawnable, enemy_systems::AI, game_systems::GameTime, gun_systems::Gun, health_systems::Invulnerable, modifier_systems::{Bounce, Explosion, LaserImpact, Split}, part_systems::Shielded, player_systems::Player, pool::{Pool, pool_recycle, register_sparse}, util::{Health, HitBox, Lifetime, Speed, TIME_STEP, WinSize}};
+
+/** Leeway past the screen edges before lasers are removed, bouncing ones turn around before reaching it */
+const LASER_MARGIN: f32 = 20.;
+/** Lasers created up front, the pool grows past it under heavy fire */
+const LASER_POOL_SIZE: usize = 512;
 
 pub struct Laser;
 
@@ -9,12 +16,70 @@ pub struct FromEnemy;
 
 pub struct Damage(pub f32, pub f32);
 
+/** Laser keeps going after a hit, remembering what it already hit */
+pub struct Piercing {
+    /** Hits left before it's used up, `None` never runs out */
+    pub remaining: Option<u32>,
+    pub hit: Vec<Entity>,
+}
+impl Piercing {
+    pub fn new(targets: u32) -> Self {
+        Self {
+            remaining: Some(targets),
+            hit: Vec::new(),
+        }
+    }
+    /** Registers a hit on `target`, true once the laser is used up */
+    pub fn hit(&mut self, target: Entity) -> bool {
+        self.hit.push(target);
+        match self.remaining {
+            Some(0) => true,
+            Some(remaining) => {
+                self.remaining = Some(remaining - 1);
+                false
+            },
+            None => false,
+        }
+    }
+}
+impl Default for Piercing {
+    fn default() -> Self {
+        Self {
+            remaining: None,
+            hit: Vec::new(),
+        }
+    }
+}
+
+/** Laser hits anything within this distance instead of only on its center */
+pub struct LaserRadius(pub f32);
+
+/** Steers towards the closest target of the other side */
+pub struct Homing {
+    /** Radians per second */
+    pub turn_rate: f32,
+    pub target: Option<Entity>,
+}
+impl Homing {
+    pub fn new(turn_rate: f32) -> Self {
+        Self {
+            turn_rate,
+            target: None,
+        }
+    }
+}
+
+/** Everything a laser may carry, stripped when it goes back to the pool */
+pub type LaserParts = (Laser, Despawnable, FromPlayer, FromEnemy, Homing, Lifetime, Piercing, Bounce, Split, Explosion, LaserRadius);
+pub type LaserPool = Pool<LaserParts>;
+
 //#region Bundles
 #[derive(Bundle)]
 pub struct LaserBundle {
     pub damage: Damage,
     pub speed: Speed,
     pub laser: Laser,
+    pub despawnable: Despawnable,
 
     #[bundle]
     pub sprite: SpriteSheetBundle
@@ -25,6 +90,7 @@ impl Default for LaserBundle {
             damage: Damage(1., 1.),
             speed: Speed(0., 500.),
             laser: Laser,
+            despawnable: Despawnable::new(LASER_MARGIN),
             sprite: SpriteSheetBundle::default()
         }
     }
@@ -32,6 +98,28 @@ impl Default for LaserBundle {
 
 //#endregion
 
+/** Adds the optional behaviours of the gun to a laser it just fired */
+pub fn insert_gun_modifiers(laser: &mut EntityCommands, gun: &Gun) {
+    if let Some(turn_rate) = gun.homing {
+        laser.insert(Homing::new(turn_rate));
+    }
+    if let Some(lifetime) = gun.lifetime {
+        laser.insert(Lifetime::from_seconds(lifetime));
+    }
+    if let Some(pierce) = gun.pierce {
+        laser.insert(Piercing::new(pierce));
+    }
+    if let Some(bounce) = gun.bounce {
+        laser.insert(Bounce(bounce));
+    }
+    if let Some(split) = &gun.split {
+        laser.insert(split.clone());
+    }
+    if let Some(explosion) = &gun.explosion {
+        laser.insert(explosion.clone());
+    }
+}
+
 //#region Laser Systems
 fn laser_movement (
     mut query: Query<(Entity, &mut Transform, &Speed, With<Laser>)>
@@ -47,38 +135,148 @@ fn laser_movement (
     });
 }
 
-fn laser_disappear(
-    mut commands: Commands,
-    win_size: Res<WinSize>,
-    mut query: Query<(Entity, &Transform, With<Laser>)>
+fn laser_homing (
+    time: Res<GameTime>,
+    mut query: Query<(&mut Transform, &mut Speed, &mut Homing, Option<&FromPlayer>), With<Laser>>,
+    enemy_query: Query<(Entity, &GlobalTransform), (With<AI>, Without<Laser>)>,
+    player_query: Query<(Entity, &Transform), (With<Player>, Without<Laser>)>,
 ) {
-    query.for_each_mut(|(laser_entity, mut transform, _)| {
-        let translation = &transform.translation;
-        if 0. > translation.y || translation.y > win_size.h {
-            commands.entity(laser_entity).despawn();
+    let delta = time.delta().as_secs_f32();
+    let enemies: Vec<(Entity, Vec2)> = enemy_query.iter()
+        .map(|(entity, transform)| (entity, transform.translation.truncate()))
+        .collect();
+    let players: Vec<(Entity, Vec2)> = player_query.iter()
+        .map(|(entity, transform)| (entity, transform.translation.truncate()))
+        .collect();
+    query.for_each_mut(|(mut transform, mut speed, mut homing, from_player)| {
+        let targets = if from_player.is_some() { &enemies } else { &players };
+        let position = transform.translation.truncate();
+
+        // Keep the current target while it exists, otherwise take the closest
+        let current = targets.iter()
+            .find(|(entity, _)| Some(*entity) == homing.target);
+        let target = current
+            .or_else(|| targets.iter().min_by(|(_, a), (_, b)| {
+                a.distance_squared(position)
+                    .partial_cmp(&b.distance_squared(position))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            }))
+            .cloned();
+        homing.target = target.map(|(entity, _)| entity);
+
+        let velocity = Vec2::new(speed.0, speed.1);
+        let mut heading = velocity.y.atan2(velocity.x);
+        if let Some((_, target)) = target {
+            let wanted = (target - position).y.atan2((target - position).x);
+            // Shortest way around, limited by the turn rate
+            let mut turn = wanted - heading;
+            while turn > PI { turn -= 2. * PI; }
+            while turn < -PI { turn += 2. * PI; }
+            let max_turn = homing.turn_rate * delta;
+            heading += turn.clamp(-max_turn, max_turn);
         }
+        let magnitude = velocity.length();
+        speed.0 = heading.cos() * magnitude;
+        speed.1 = heading.sin() * magnitude;
+        // Sprites point up
+        transform.rotation = Quat::from_rotation_z(heading - PI / 2.);
     });
 }
 
 fn laser_hit(
     mut commands: Commands,
-    mut query: Query<(&mut Health, &HitBox, &Transform, With<AI>)>,
-    mut laser_query: Query<(Entity, &Transform, &Damage, With<Laser>, Option<&FromPlayer>, Option<&FromEnemy>)>
+    mut pool: ResMut<LaserPool>,
+    mut impacts: EventWriter<LaserImpact>,
+    mut query: Query<(Entity, &mut Health, &HitBox, &GlobalTransform, Option<&Shielded>, With<AI>)>,
+    mut laser_query: Query<(Entity, &Transform, &Damage, &Speed, With<Laser>, Option<&FromPlayer>, Option<&mut Piercing>, Option<&LaserRadius>, Option<&Split>, Option<&Explosion>)>
 ) {
-    query.for_each_mut(|(mut health, hitbox, transform, _)|{
+    // Lasers used up this frame, so they can't hit twice before despawning
+    let mut spent: Vec<Entity> = Vec::new();
+    query.for_each_mut(|(entity, mut health, hitbox, transform, shielded, _)|{
         laser_query.for_each_mut(|(
             laser_entity,
             laser_transform,
             damage,
+            speed,
             _,
             from_player,
-            from_enemy
+            piercing,
+            radius,
+            split,
+            explosion
         )| {
-            if let Some(from_player) = from_player {
-                if (hitbox.contains(&transform.translation,&laser_transform.translation)) {
-                    health.0 -= damage.0;
-                    commands.entity(laser_entity).despawn();
-                }
+            if from_player.is_none() || spent.contains(&laser_entity) {
+                return;
+            }
+            let radius = radius.map_or(0., |radius| radius.0);
+            if !hitbox.overlaps(&transform.translation, &laser_transform.translation, radius) {
+                return;
+            }
+            let used_up = match piercing {
+                Some(mut piercing) => {
+                    if piercing.hit.contains(&entity) {
+                        return;
+                    }
+                    piercing.hit(entity)
+                },
+                None => true,
+            };
+            // Shielded cores soak up lasers without taking damage
+            if shielded.is_none() {
+                health.0 -= damage.0;
+            }
+            if used_up {
+                spent.push(laser_entity);
+                impacts.send(LaserImpact {
+                    translation: laser_transform.translation,
+                    speed: *speed,
+                    damage: damage.0,
+                    from_player: true,
+                    target: Some(entity),
+                    split: split.cloned(),
+                    explosion: explosion.cloned(),
+                });
+                pool.release(&mut commands, laser_entity);
+            }
+        });
+    });
+}
+
+fn laser_hit_player(
+    mut commands: Commands,
+    mut pool: ResMut<LaserPool>,
+    mut impacts: EventWriter<LaserImpact>,
+    mut query: Query<(Entity, &mut Health, &HitBox, &Transform), (With<Player>, Without<Invulnerable>)>,
+    mut laser_query: Query<(Entity, &Transform, &Damage, &Speed, Option<&mut Piercing>, Option<&Split>, Option<&Explosion>), (With<Laser>, With<FromEnemy>)>
+) {
+    let mut spent: Vec<Entity> = Vec::new();
+    query.for_each_mut(|(entity, mut health, hitbox, transform)| {
+        laser_query.for_each_mut(|(laser_entity, laser_transform, damage, speed, piercing, split, explosion)| {
+            if spent.contains(&laser_entity) || !hitbox.contains(&transform.translation, &laser_transform.translation) {
+                return;
+            }
+            let used_up = match piercing {
+                Some(mut piercing) => {
+                    if piercing.hit.contains(&entity) {
+                        return;
+                    }
+                    piercing.hit(entity)
+                },
+                None => true,
+            };
+            health.0 -= damage.0;
+            if used_up {
+                spent.push(laser_entity);
+                impacts.send(LaserImpact {
+                    translation: laser_transform.translation,
+                    speed: *speed,
+                    damage: damage.0,
+                    from_player: false,
+                    target: Some(entity),
+                    split: split.cloned(),
+                    explosion: explosion.cloned(),
+                });
+                pool.release(&mut commands, laser_entity);
             }
         });
     });
@@ -86,13 +284,30 @@ fn laser_hit(
 
 //#endregion
 
+fn laser_pool_setup (
+    mut commands: Commands,
+    mut pool: ResMut<LaserPool>,
+) {
+    pool.reserve(&mut commands, LASER_POOL_SIZE, LaserBundle::default);
+}
+
 pub struct LaserSystemsPlugin;
 
 impl Plugin for LaserSystemsPlugin {
     fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        // Markers every laser gets, the rarer modifiers stay in the tables
+        let world = app.world_mut();
+        register_sparse::<Laser>(world);
+        register_sparse::<Despawnable>(world);
+        register_sparse::<FromPlayer>(world);
+        register_sparse::<FromEnemy>(world);
         app
+            .insert_resource(LaserPool::default())
+            .add_startup_system(laser_pool_setup.system())
+            .add_system_to_stage(CoreStage::PostUpdate, pool_recycle::<LaserParts>.system())
             .add_system(laser_movement.system())
+            .add_system(laser_homing.system())
             .add_system(laser_hit.system())
-            .add_system(laser_disappear.system());
+            .add_system(laser_hit_player.system());
         }
 }
\ No newline at end of file
@@ -10,24 +10,64 @@ mod game_systems;
 mod map_systems;
 mod collision_systems;
 mod health_systems;
+mod bomb_systems;
+mod pickup_systems;
+mod option_systems;
+mod charge_systems;
+mod beam_systems;
+mod modifier_systems;
+mod path_systems;
+mod formation_systems;
+mod boss_systems;
+mod part_systems;
+mod cleanup_systems;
+mod pool;
+mod difficulty_systems;
+mod score_systems;
+mod title_systems;
+mod stage_systems;
+mod daily_systems;
+mod practice_systems;
+mod attack_systems;
+mod replay_systems;
 
 use assets_config::{ENEMY_SPRITESHEET_1, FONT_TTF, LASER_SPRITE, PLAYER_SPRITE, PLAYER_SPRITESHEET, PROJECTILE_SPRITESHEET};
+use attack_systems::AttackPlugin;
 use bevy::diagnostic::LogDiagnosticsPlugin;
 use bevy::math::{Vec2};
-use bevy::prelude::{App, AssetServer, Assets, ClearColor, Color, Commands, Handle, IntoSystem, OrthographicCameraBundle, Res, ResMut, Texture, Transform};
+use bevy::prelude::{App, AssetServer, Assets, ClearColor, Color, Commands, CoreStage, Handle, IntoSystem, OrthographicCameraBundle, Res, ResMut, Texture, Transform};
 use bevy::DefaultPlugins;
 use bevy::render::camera::{Camera, DepthCalculation, OrthographicProjection};
 use bevy::render::render_graph::base::camera::CAMERA_2D;
 use bevy::sprite::{ColorMaterial, TextureAtlas};
 use bevy::text::Font;
 use bevy::window::{WindowDescriptor, WindowMode, Windows};
+use beam_systems::BeamPlugin;
+use bomb_systems::BombPlugin;
+use boss_systems::BossPlugin;
+use charge_systems::ChargePlugin;
+use cleanup_systems::CleanupPlugin;
+use collision_systems::CollisionPlugin;
+use daily_systems::DailyPlugin;
+use difficulty_systems::DifficultyPlugin;
 use enemy_systems::EnemyPlugin;
+use formation_systems::FormationPlugin;
 use game_systems::GameSystemsPlugin;
 use gun_systems::GunSystemsPlugin;
 use health_systems::HealthPlugin;
 use laser_systems::{LaserSystemsPlugin};
 use map_systems::MapPlugin;
+use modifier_systems::ModifierPlugin;
+use option_systems::OptionPlugin;
+use part_systems::PartPlugin;
+use path_systems::PathPlugin;
+use pickup_systems::PickupPlugin;
 use player_systems::{PlayerPlugin};
+use practice_systems::PracticePlugin;
+use replay_systems::ReplayPlugin;
+use score_systems::ScorePlugin;
+use stage_systems::StagePlugin;
+use title_systems::TitlePlugin;
 use util::{Materials, WinSize};
 
 //#region Startup Systems
@@ -118,15 +158,36 @@ fn main() {
         //.add_plugin(LogDiagnosticsPlugin::default())
         //.add_plugin(bevy::diagnostic::EntityCountDiagnosticsPlugin::default())
         //.add_plugin(bevy::wgpu::diagnostic::WgpuResourceDiagnosticsPlugin::default())
-        .add_system(bevy::input::system::exit_on_esc_system.system())
+        // Outside of the gameplay stage so it works from the menus too
+        .add_system_to_stage(CoreStage::PreUpdate, bevy::input::system::exit_on_esc_system.system())
         .add_startup_system(setup.system())
         .add_startup_system(load_assets.system())
         .add_plugin(GameSystemsPlugin)
+        .add_plugin(DifficultyPlugin)
+        .add_plugin(ScorePlugin)
+        .add_plugin(TitlePlugin)
+        .add_plugin(StagePlugin)
+        .add_plugin(DailyPlugin)
+        .add_plugin(PracticePlugin)
+        .add_plugin(AttackPlugin)
+        .add_plugin(ReplayPlugin)
         .add_plugin(PlayerPlugin)
         .add_plugin(GunSystemsPlugin)
         .add_plugin(LaserSystemsPlugin)
+        .add_plugin(ModifierPlugin)
+        .add_plugin(CleanupPlugin)
         .add_plugin(HealthPlugin)
+        .add_plugin(CollisionPlugin)
+        .add_plugin(BombPlugin)
+        .add_plugin(PickupPlugin)
+        .add_plugin(OptionPlugin)
+        .add_plugin(ChargePlugin)
+        .add_plugin(BeamPlugin)
         .add_plugin(EnemyPlugin)
+        .add_plugin(PathPlugin)
+        .add_plugin(FormationPlugin)
+        .add_plugin(PartPlugin)
+        .add_plugin(BossPlugin)
         .add_plugin(MapPlugin)
         .run(); // Start the app
 }
@@ -1,18 +1,166 @@
-use bevy::{math::Vec3, prelude::{Commands, IntoSystem, Plugin, Res, ResMut, SpriteSheetBundle, SystemStage, Transform}, sprite::TextureAtlasSprite};
+use std::{f32::consts::PI, sync::Arc};
 
-use crate::{enemy_systems::{AICircle, AIEntrance, EnemyBundle, EntranceDirections}, game_systems::GameState, util::{Materials, WinSize}};
+use bevy::{log::warn, math::{Vec2, Vec3}, prelude::{Commands, IntoSystem, Plugin, Res, ResMut, SpriteSheetBundle, SystemStage, Transform}, sprite::TextureAtlasSprite};
+
+use crate::{beam_systems::{BeamCycle, BeamEmitter}, collision_systems::ContactDamage, difficulty_systems::Rank, enemy_systems::{AIBehavior, AICircle, AIEntrance, AIHorizontal, EnemyBundle, EntranceDirections, Turret}, formation_systems::{Formation, FormationLayout, spawn_formation}, game_systems::{GameMode, GameState, METERS_PER_SECOND, PIXELS_PER_METER, StageStatus}, gun_systems::{Gun, GunAim, GunCollection, GunCooldown}, modifier_systems::{Explosion, Split}, part_systems::{spawn_armored, spawn_snake}, path_systems::{AIPath, SampledPath, Spline}, stage_systems::STAGES, util::{Health, HitBox, Materials, Random, Rng, Speed, WinSize}};
+
+/** Meters between spawns at the lowest rank */
+const SPAWN_GAP: f32 = 40.;
+const FIRST_SPAWN: f32 = 20.;
+/** Cost of the waves an endless spawn can afford at the start */
+const ENDLESS_BUDGET: f32 = 2.;
+/** Meters for the endless budget to grow by one */
+const ENDLESS_METERS_PER_BUDGET: f32 = 200.;
+/** Waves spawned together at most in endless */
+const ENDLESS_MAX_WAVES: usize = 3;
+/** Seconds a circling column goes round before leaving */
+const CIRCLING_SECONDS: f32 = 12.;
+
+/** A group of enemies spawned together, one per line of a stage's wave file */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Wave {
+    /** One enemy, armed and moving depending on the spawn count */
+    Single,
+    Grid,
+    Vee,
+    Armored,
+    Snake,
+    Kamikazes,
+    Turrets,
+    Column,
+    /** A column going round in a loop before leaving */
+    Circling,
+}
+impl Wave {
+    pub const ALL: [Wave; 9] = [Wave::Single, Wave::Grid, Wave::Vee, Wave::Armored, Wave::Snake, Wave::Kamikazes, Wave::Turrets, Wave::Column, Wave::Circling];
+
+    /** How much of the endless budget the wave takes */
+    pub fn cost(&self) -> f32 {
+        match self {
+            Wave::Single => 1.,
+            Wave::Kamikazes | Wave::Turrets | Wave::Column => 2.,
+            Wave::Vee | Wave::Snake | Wave::Circling => 3.,
+            Wave::Grid | Wave::Armored => 4.,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "single" => Some(Wave::Single),
+            "grid" => Some(Wave::Grid),
+            "vee" => Some(Wave::Vee),
+            "armored" => Some(Wave::Armored),
+            "snake" => Some(Wave::Snake),
+            "kamikazes" => Some(Wave::Kamikazes),
+            "turrets" => Some(Wave::Turrets),
+            "column" => Some(Wave::Column),
+            "circling" => Some(Wave::Circling),
+            _ => None,
+        }
+    }
+
+    /** Wave names one per line, `#` starts a comment. Unknown names are skipped */
+    pub fn parse(content: &str) -> Vec<Wave> {
+        content
+            .lines()
+            .map(|line| line.split('#').next().unwrap().trim())
+            .filter(|line| !line.is_empty())
+            .filter_map(|name| {
+                let wave = Self::from_name(name);
+                if wave.is_none() {
+                    warn!("Unknown wave {}", name);
+                }
+                wave
+            })
+            .collect()
+    }
+
+    /** Mix used when a stage has no waves, cycling through everything */
+    fn mixed(spawn_count: u32) -> Self {
+        if spawn_count % 10 == 8 {
+            return if spawn_count % 20 == 8 { Wave::Grid } else { Wave::Vee };
+        }
+        if spawn_count % 9 == 6 {
+            return Wave::Armored;
+        }
+        if spawn_count % 11 == 9 {
+            return Wave::Snake;
+        }
+        if spawn_count % 6 == 3 {
+            return Wave::Kamikazes;
+        }
+        if spawn_count % 7 == 5 {
+            return Wave::Turrets;
+        }
+        if spawn_count % 5 == 1 {
+            return if spawn_count % 10 == 1 { Wave::Column } else { Wave::Circling };
+        }
+        Wave::Single
+    }
+
+    /** Spawned together, the two would end up on top of each other */
+    fn overlaps(&self, other: &Wave) -> bool {
+        let formation = |wave: &Wave| matches!(wave, Wave::Grid | Wave::Vee);
+        self == other || (formation(self) && formation(other))
+    }
+
+    /** Random waves, together worth up to the budget of the distance reached */
+    fn endless(distance: f32, random: &mut Random) -> Vec<Wave> {
+        let mut budget = ENDLESS_BUDGET + distance / ENDLESS_METERS_PER_BUDGET;
+        let mut waves = Vec::new();
+        while waves.len() < ENDLESS_MAX_WAVES {
+            let affordable: Vec<Wave> = Self::ALL.iter()
+                .cloned()
+                .filter(|wave| wave.cost() <= budget && !waves.iter().any(|other| wave.overlaps(other)))
+                .collect();
+            if affordable.is_empty() {
+                break;
+            }
+            let wave = affordable[random.next_u32() as usize % affordable.len()];
+            budget -= wave.cost();
+            waves.push(wave);
+        }
+        waves
+    }
+}
 
 pub struct MapState {
     pub last_spawn: f32,
+    pub spawn_count: u32,
+    /** Waves of the stage, looping once they run out */
+    pub waves: Vec<Wave>,
+}
+impl MapState {
+    pub fn new(waves: Vec<Wave>) -> Self {
+        Self {
+            last_spawn: FIRST_SPAWN - SPAWN_GAP,
+            spawn_count: 0,
+            waves,
+        }
+    }
+
+    /** Where the wave at `index` spawns at the lowest rank */
+    pub fn wave_distance(index: u32) -> f32 {
+        FIRST_SPAWN + index as f32 * SPAWN_GAP
+    }
+
+    /** Waves spawned before `distance` at the lowest rank */
+    pub fn waves_before(distance: f32) -> u32 {
+        ((distance - FIRST_SPAWN) / SPAWN_GAP).ceil().max(0.) as u32
+    }
+
+    /** Skips the spawns before `distance` as if they happened at the lowest rank */
+    pub fn skip_to(&mut self, distance: f32) {
+        let skipped = Self::waves_before(distance);
+        self.spawn_count = skipped;
+        self.last_spawn = Self::wave_distance(skipped) - SPAWN_GAP;
+    }
 }
 
 fn map_setup (
     mut commands: Commands
 ) {
-    commands
-        .insert_resource(MapState {
-            last_spawn: 0.,
-        })
+    commands.insert_resource(MapState::new(Wave::parse(STAGES[0].waves)));
 }
 
 fn enemy_spawn (
@@ -20,10 +168,231 @@ fn enemy_spawn (
     assets: Res<Materials>,
     game_state: Res<GameState>,
     mut map_state: ResMut<MapState>,
+    mut rng: ResMut<Rng>,
+    mode: Res<GameMode>,
+    rank: Res<Rank>,
     win_size: Res<WinSize>
 ) {
-    if map_state.last_spawn + 20. < game_state.distance.0 {
-        map_state.last_spawn = game_state.distance.0 + 20.;
+    if game_state.status != StageStatus::Running {
+        return;
+    }
+    if map_state.last_spawn + SPAWN_GAP / rank.spawn_rate() < game_state.distance.0 {
+        map_state.last_spawn = game_state.distance.0;
+        map_state.spawn_count += 1;
+        let count = map_state.spawn_count;
+        // Spawn count picks the variants of authored waves, endless ones are random
+        let (waves, variant) = match (*mode, map_state.waves.len()) {
+            (mode, _) if mode.endless() => (Wave::endless(game_state.distance.0, &mut rng.spawns), rng.spawns.next_u32()),
+            (_, 0) => (vec![Wave::mixed(count)], count),
+            (_, len) => (vec![map_state.waves[(count as usize - 1) % len]], count),
+        };
+        for wave in waves {
+            spawn_wave(&mut commands, &assets, &win_size, &mut rng.spawns, wave, variant);
+        }
+    }
+}
+
+fn spawn_wave (
+    commands: &mut Commands,
+    assets: &Materials,
+    win_size: &WinSize,
+    random: &mut Random,
+    wave: Wave,
+    variant: u32,
+) {
+    match wave {
+        Wave::Single => spawn_single(commands, assets, win_size, variant),
+        Wave::Grid | Wave::Vee => {
+            let layout = if wave == Wave::Grid {
+                FormationLayout::Grid {
+                    columns: 5,
+                    rows: 2,
+                    spacing: Vec2::new(70., 60.),
+                }
+            } else {
+                FormationLayout::V {
+                    count: 7,
+                    spacing: Vec2::new(60., 45.),
+                }
+            };
+            let formation = Formation::new(Vec2::new(win_size.half_w, win_size.h - 160.));
+            spawn_formation(commands, assets, win_size, layout, formation);
+        }
+        Wave::Armored => spawn_armored(commands, assets, win_size),
+        Wave::Snake => spawn_snake(commands, assets, win_size, 6, variant % 2 == 0),
+        Wave::Kamikazes => spawn_kamikazes(commands, assets, win_size, random),
+        Wave::Turrets => spawn_turrets(commands, assets, win_size),
+        Wave::Column => spawn_column(commands, assets, win_size, variant % 2 == 0, false),
+        Wave::Circling => spawn_column(commands, assets, win_size, variant % 2 == 0, true),
+    }
+}
+
+/** One enemy, its guns and movement picked by `variant` */
+fn spawn_single (
+    commands: &mut Commands,
+    assets: &Materials,
+    win_size: &WinSize,
+    variant: u32,
+) {
+    let direction = match variant % 3 {
+        0 => EntranceDirections::Left,
+        1 => EntranceDirections::Up,
+        _ => EntranceDirections::Right,
+    };
+    // Every fourth enemy, offset by two, strafes across the top instead
+    let strafing = variant % 4 == 2;
+    let translation = if strafing {
+        Vec3::new(win_size.half_w, win_size.h + 100., 10.)
+    } else {
+        direction.spawn_point(win_size)
+    };
+    let mut enemy = commands.spawn_bundle(EnemyBundle {
+        sprite: SpriteSheetBundle {
+            texture_atlas: assets.enemy_atlas.clone(),
+            sprite: TextureAtlasSprite {
+                index: 0,
+                ..Default::default() 
+            },
+            transform: Transform {
+                translation,
+                scale: Vec3::new(4., 4., 1.),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        speed: if strafing { Speed(0., 80.) } else { Speed(10., 10.) },
+        behavior: if strafing { AIBehavior::strafing() } else { AIBehavior::default() },
+        weapon: GunCollection {
+            guns: Box::new([
+                Gun {
+                    cooldown: GunCooldown(0., 1.),
+                    initial_speed: Speed(0., -350.),
+                    // Strafers lead their shots, the rest aim roughly at the player
+                    aim: if strafing { GunAim::Lead } else { GunAim::AtPlayer },
+                    inaccuracy: if strafing { 0.1 } else { 0.4 },
+                    ..Default::default()
+                }
+            ])
+        },
+        ..Default::default()
+    });
+    if strafing {
+        enemy.insert(AIHorizontal {
+            x_origin: win_size.half_w,
+            y_origin: win_size.h - 120.,
+            amplitude: win_size.half_w - 80.,
+            frequency: 1.2,
+        });
+    } else {
+        enemy
+            .insert(AIEntrance { direction })
+            .insert(AICircle {
+                 x_origin: win_size.half_w, 
+                 y_origin: win_size.half_h + 200., 
+                 x_radius: 100., y_radius: 100. 
+            });
+    }
+    // Special guns replace the regular one, the rarest wins when several apply
+    if variant % 7 == 0 {
+        // Explosive lasers
+        enemy.insert(GunCollection {
+            guns: Box::new([
+                Gun {
+                    cooldown: GunCooldown(0., 1.5),
+                    initial_speed: Speed(0., -250.),
+                    explosion: Some(Explosion {
+                        radius: 40.,
+                        damage: 1.,
+                    }),
+                    ..Default::default()
+                }
+            ])
+        });
+    } else if variant % 5 == 0 {
+        // Bouncing lasers that burst apart
+        enemy.insert(GunCollection {
+            guns: Box::new([
+                Gun {
+                    cooldown: GunCooldown(0., 2.5),
+                    initial_speed: Speed(-120., -220.),
+                    bounce: Some(2),
+                    split: Some(Split::after(5, PI / 2., 1.5)),
+                    ..Default::default()
+                }
+            ])
+        });
+    } else if variant % 4 == 0 {
+        // Slow homing lasers
+        enemy.insert(GunCollection {
+            guns: Box::new([
+                Gun {
+                    cooldown: GunCooldown(0., 2.),
+                    initial_speed: Speed(0., -200.),
+                    homing: Some(1.5),
+                    lifetime: Some(4.),
+                    ..Default::default()
+                }
+            ])
+        });
+    }
+    // Every third enemy also sweeps a beam down
+    if variant % 3 == 0 {
+        enemy
+            .insert(BeamEmitter {
+                direction: Vec2::new(0., -1.),
+                width: 16.,
+                dps: 20.,
+                ..Default::default()
+            })
+            .insert(BeamCycle::default());
+    }
+}
+
+/** Three quick enemies that lock on and ram the player */
+fn spawn_kamikazes (
+    commands: &mut Commands,
+    assets: &Materials,
+    win_size: &WinSize,
+    random: &mut Random,
+) {
+    for i in 0..3 {
+        let x = random.range(80., win_size.w - 80.);
+        commands.spawn_bundle(EnemyBundle {
+            sprite: SpriteSheetBundle {
+                texture_atlas: assets.enemy_atlas.clone(),
+                sprite: TextureAtlasSprite {
+                    index: 0,
+                    ..Default::default() 
+                },
+                transform: Transform {
+                    translation: Vec3::new(x, win_size.h + 60. + i as f32 * 50., 10.),
+                    scale: Vec3::new(2.5, 2.5, 1.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            behavior: AIBehavior::kamikaze(),
+            weapon: GunCollection {
+                guns: Box::new([])
+            },
+            hitbox: HitBox {
+                rect: Vec3::new(30., 30., 1.)
+            },
+            contact: ContactDamage(30.),
+            ..Default::default()
+        });
+    }
+}
+
+/** Two turrets on the ground, scrolling by with the background */
+fn spawn_turrets (
+    commands: &mut Commands,
+    assets: &Materials,
+    win_size: &WinSize,
+) {
+    // Long enough to scroll past the whole screen
+    let duration = (win_size.h + 200.) / (METERS_PER_SECOND * PIXELS_PER_METER);
+    for x in [win_size.w * 0.25, win_size.w * 0.75].iter() {
         commands
             .spawn_bundle(EnemyBundle {
                 sprite: SpriteSheetBundle {
@@ -33,21 +402,101 @@ fn enemy_spawn (
                         ..Default::default() 
                     },
                     transform: Transform {
-                        translation: Vec3::new(win_size.w + 100., win_size.h - 75., 10.),
-                        scale: Vec3::new(4., 4., 1.),
+                        translation: Vec3::new(*x, win_size.h + 50., 5.),
+                        scale: Vec3::new(3., 3., 1.),
                         ..Default::default()
                     },
                     ..Default::default()
                 },
+                behavior: AIBehavior::turret(duration),
+                weapon: GunCollection {
+                    guns: Box::new([
+                        Gun {
+                            cooldown: GunCooldown(1., 1.5),
+                            initial_speed: Speed(0., -300.),
+                            aim: GunAim::Facing(0.),
+                            ..Default::default()
+                        }
+                    ])
+                },
+                health: Health(4., 4.),
                 ..Default::default()
             })
-            .insert(AIEntrance { 
-                direction: EntranceDirections::Left 
+            .insert(Turret { turn_rate: 1.5 })
+            // On the ground, the player flies over it
+            .remove::<ContactDamage>();
+    }
+}
+
+/** Four enemies snaking down the screen one behind the other, or going round a loop when `circling` */
+fn spawn_column (
+    commands: &mut Commands,
+    assets: &Materials,
+    win_size: &WinSize,
+    mirror: bool,
+    circling: bool,
+) {
+    let (w, h) = (win_size.w, win_size.h);
+    let points = if circling {
+        // Closed, the column flies in along the first segment from above the screen
+        vec![
+            Vec2::new(w * 0.5, h * 0.85),
+            Vec2::new(w * 0.8, h * 0.7),
+            Vec2::new(w * 0.65, h * 0.45),
+            Vec2::new(w * 0.35, h * 0.45),
+            Vec2::new(w * 0.2, h * 0.7),
+            Vec2::new(w * 0.5, h * 0.85),
+        ]
+    } else {
+        vec![
+            Vec2::new(w * 0.15, h + 60.),
+            Vec2::new(w * 0.2, h * 0.7),
+            Vec2::new(w * 0.75, h * 0.6),
+            Vec2::new(w * 0.8, h * 0.35),
+            Vec2::new(w * 0.3, h * 0.25),
+            Vec2::new(w * 0.2, -100.),
+        ]
+    };
+    let path = Arc::new(SampledPath::new(&Spline::CatmullRom(points)));
+    let behavior = || if circling {
+        AIBehavior { lifetime: Some(CIRCLING_SECONDS), ..AIBehavior::following_path() }
+    } else {
+        AIBehavior::following_path()
+    };
+    for index in 0..4 {
+        commands
+            .spawn_bundle(EnemyBundle {
+                sprite: SpriteSheetBundle {
+                    texture_atlas: assets.enemy_atlas.clone(),
+                    sprite: TextureAtlasSprite {
+                        index: 0,
+                        ..Default::default() 
+                    },
+                    transform: Transform {
+                        translation: Vec3::new(w * 0.15, h + 100., 10.),
+                        scale: Vec3::new(3., 3., 1.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                behavior: behavior(),
+                weapon: GunCollection {
+                    guns: Box::new([
+                        Gun {
+                            cooldown: GunCooldown(index as f32 * 0.5, 2.),
+                            initial_speed: Speed(0., -300.),
+                            aim: GunAim::Facing(0.),
+                            ..Default::default()
+                        }
+                    ])
+                },
+                ..Default::default()
             })
-            .insert(AICircle {
-                 x_origin: win_size.half_w, 
-                 y_origin: win_size.half_h + 200., 
-                 x_radius: 100., y_radius: 100. 
+            .insert(AIPath {
+                face_along: true,
+                looping: circling,
+                mirror,
+                ..AIPath::new(path.clone(), 220.).in_column(index, 70.)
             });
     }
 }
@@ -60,4 +509,29 @@ impl Plugin for MapPlugin {
             .add_startup_system(map_setup.system())
             .add_system(enemy_spawn.system());
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skip_to_the_start_changes_nothing() {
+        let mut map_state = MapState::new(Vec::new());
+        map_state.skip_to(0.);
+        assert_eq!(map_state.spawn_count, 0);
+        assert_eq!(map_state.last_spawn, MapState::new(Vec::new()).last_spawn);
+    }
+
+    #[test]
+    fn skip_to_a_wave_spawns_it_next() {
+        let mut map_state = MapState::new(Vec::new());
+        map_state.skip_to(MapState::wave_distance(3));
+        assert_eq!(map_state.spawn_count, 3);
+        assert_eq!(map_state.last_spawn + SPAWN_GAP, MapState::wave_distance(3));
+        // Just past it, that wave is skipped too
+        map_state.skip_to(MapState::wave_distance(3) + 1.);
+        assert_eq!(map_state.spawn_count, 4);
+        assert_eq!(map_state.last_spawn + SPAWN_GAP, MapState::wave_distance(4));
+    }
+}
@@ -0,0 +1,226 @@
+use bevy::{core::Timer, math::{Vec2, Vec3}, prelude::{Assets, Commands, Entity, EventReader, GlobalTransform, Handle, IntoSystem, Plugin, Query, Res, ResMut, SpriteBundle, SpriteSheetBundle, Transform, With, Without}, render::color::Color, sprite::{ColorMaterial, Sprite, TextureAtlasSprite}};
+
+use crate::{enemy_systems::AI, game_systems::GameTime, health_systems::Invulnerable, laser_systems::{Damage, FromEnemy, FromPlayer, Laser, LaserBundle, LaserPool, Piercing}, part_systems::Shielded, player_systems::Player, util::{Health, Materials, Speed, WinSize}};
+
+//#region Components
+/** Bounces off the edges of the screen this many more times */
+pub struct Bounce(pub u32);
+
+/** Breaks into fragments, on impact or once `delay` seconds have passed */
+#[derive(Clone)]
+pub struct Split {
+    pub count: u32,
+    /** Angle in radians the fragments fan out over */
+    pub spread: f32,
+    pub delay: Option<f32>,
+    pub elapsed: f32,
+}
+impl Split {
+    pub fn on_impact(count: u32, spread: f32) -> Self {
+        Self { count, spread, delay: None, elapsed: 0. }
+    }
+    pub fn after(count: u32, spread: f32, delay: f32) -> Self {
+        Self { count, spread, delay: Some(delay), elapsed: 0. }
+    }
+}
+
+/** Damages everything of the other side within `radius` on impact */
+#[derive(Clone)]
+pub struct Explosion {
+    pub radius: f32,
+    pub damage: f32,
+}
+
+pub struct ExplosionEffect {
+    pub timer: Timer,
+}
+//#endregion
+
+//#region Events
+/** Sent when a laser is used up by a hit */
+pub struct LaserImpact {
+    pub translation: Vec3,
+    pub speed: Speed,
+    pub damage: f32,
+    pub from_player: bool,
+    /** What the laser hit, so fragments don't hit it again */
+    pub target: Option<Entity>,
+    pub split: Option<Split>,
+    pub explosion: Option<Explosion>,
+}
+//#endregion
+
+/** Fires `split.count` smaller lasers from `translation`, fanned around `speed` */
+pub fn spawn_fragments (
+    commands: &mut Commands,
+    pool: &mut LaserPool,
+    materials: &Materials,
+    translation: Vec3,
+    speed: &Speed,
+    damage: f32,
+    from_player: bool,
+    split: &Split,
+    ignore: Option<Entity>,
+) {
+    let velocity = Vec2::new(speed.0, speed.1);
+    let heading = velocity.y.atan2(velocity.x);
+    let magnitude = velocity.length();
+    for i in 0..split.count {
+        let step = if split.count > 1 {
+            i as f32 / (split.count - 1) as f32 - 0.5
+        } else {
+            0.
+        };
+        let angle = heading + split.spread * step;
+        let mut fragment = pool.spawn(commands, LaserBundle {
+            sprite: SpriteSheetBundle {
+                texture_atlas: materials.projectile_atlas.clone(),
+                sprite: TextureAtlasSprite {
+                    index: if from_player { 0 } else { 2 },
+                    ..Default::default()
+                },
+                transform: Transform {
+                    translation: Vec3::new(translation.x, translation.y, 0.),
+                    scale: Vec3::new(1.5, 1.5, 1.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            speed: Speed(angle.cos() * magnitude, angle.sin() * magnitude),
+            damage: Damage(damage / 2., damage / 2.),
+            ..Default::default()
+        });
+        if from_player {
+            fragment.insert(FromPlayer);
+        } else {
+            fragment.insert(FromEnemy);
+        }
+        if let Some(ignore) = ignore {
+            fragment.insert(Piercing {
+                remaining: Some(0),
+                hit: vec![ignore],
+            });
+        }
+    }
+}
+
+fn laser_bounce (
+    win_size: Res<WinSize>,
+    query: Query<(&mut Transform, &mut Speed, &mut Bounce), With<Laser>>,
+) {
+    query.for_each_mut(|(mut transform, mut speed, mut bounce)| {
+        if bounce.0 == 0 {
+            return;
+        }
+        let translation = &mut transform.translation;
+        let out_x = (translation.x < 0. && speed.0 < 0.) || (translation.x > win_size.w && speed.0 > 0.);
+        let out_y = (translation.y < 0. && speed.1 < 0.) || (translation.y > win_size.h && speed.1 > 0.);
+        if out_x {
+            speed.0 = -speed.0;
+            translation.x = translation.x.clamp(0., win_size.w);
+        }
+        if out_y {
+            speed.1 = -speed.1;
+            translation.y = translation.y.clamp(0., win_size.h);
+        }
+        if out_x || out_y {
+            bounce.0 -= 1;
+        }
+    });
+}
+
+fn laser_split_delay (
+    mut commands: Commands,
+    mut pool: ResMut<LaserPool>,
+    time: Res<GameTime>,
+    materials: Res<Materials>,
+    query: Query<(Entity, &Transform, &Speed, &Damage, &mut Split, Option<&FromPlayer>), With<Laser>>,
+) {
+    query.for_each_mut(|(entity, transform, speed, damage, mut split, from_player)| {
+        let delay = match split.delay {
+            Some(delay) => delay,
+            None => return,
+        };
+        split.elapsed += time.delta().as_secs_f32();
+        if split.elapsed >= delay {
+            spawn_fragments(&mut commands, &mut pool, &materials, transform.translation, speed, damage.0, from_player.is_some(), &split, None);
+            pool.release(&mut commands, entity);
+        }
+    });
+}
+
+fn laser_impact (
+    mut commands: Commands,
+    mut pool: ResMut<LaserPool>,
+    mut impacts: EventReader<LaserImpact>,
+    materials: Res<Materials>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+    enemy_query: Query<(&GlobalTransform, &mut Health), (With<AI>, Without<Player>, Without<Shielded>)>,
+    player_query: Query<(&GlobalTransform, &mut Health), (With<Player>, Without<AI>, Without<Invulnerable>)>,
+) {
+    for impact in impacts.iter() {
+        if let Some(split) = &impact.split {
+            if split.delay.is_none() {
+                spawn_fragments(&mut commands, &mut pool, &materials, impact.translation, &impact.speed, impact.damage, impact.from_player, split, impact.target);
+            }
+        }
+        if let Some(explosion) = &impact.explosion {
+            let center = impact.translation.truncate();
+            let mut damage = |(transform, mut health): (&GlobalTransform, bevy::prelude::Mut<Health>)| {
+                if transform.translation.truncate().distance(center) <= explosion.radius {
+                    health.0 -= explosion.damage;
+                }
+            };
+            if impact.from_player {
+                enemy_query.for_each_mut(&mut damage);
+            } else {
+                player_query.for_each_mut(&mut damage);
+            }
+            commands
+                .spawn_bundle(SpriteBundle {
+                    material: color_materials.add(Color::rgba(1., 0.6, 0.2, 0.7).into()),
+                    sprite: Sprite::new(Vec2::new(explosion.radius * 2., explosion.radius * 2.)),
+                    transform: Transform {
+                        translation: Vec3::new(impact.translation.x, impact.translation.y, 40.),
+                        scale: Vec3::new(0.2, 0.2, 1.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(ExplosionEffect {
+                    timer: Timer::from_seconds(0.3, false),
+                });
+        }
+    }
+}
+
+fn explosion_effect_update (
+    mut commands: Commands,
+    time: Res<GameTime>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    query: Query<(Entity, &mut ExplosionEffect, &mut Transform, &Handle<ColorMaterial>)>,
+) {
+    query.for_each_mut(|(entity, mut effect, mut transform, material)| {
+        effect.timer.tick(time.delta());
+        let t = effect.timer.percent();
+        transform.scale = Vec3::new(0.2 + t * 0.8, 0.2 + t * 0.8, 1.);
+        if let Some(material) = materials.get_mut(material) {
+            material.color.set_a(0.7 * (1. - t));
+        }
+        if effect.timer.finished() {
+            commands.entity(entity).despawn();
+        }
+    });
+}
+
+pub struct ModifierPlugin;
+impl Plugin for ModifierPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .add_event::<LaserImpact>()
+            .add_system(laser_bounce.system())
+            .add_system(laser_split_delay.system())
+            .add_system(laser_impact.system())
+            .add_system(explosion_effect_update.system());
+    }
+}
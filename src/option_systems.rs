@@ -0,0 +1,181 @@
+use std::{collections::VecDeque, f32::consts::PI};
+
+use bevy::{input::Input, math::Vec3, prelude::{BuildChildren, Changed, Children, Commands, DespawnRecursiveExt, Entity, IntoSystem, KeyCode, Plugin, Query, Res, SpriteSheetBundle, Transform, With, Without}, sprite::TextureAtlasSprite};
+
+use crate::{game_systems::GameTime, gun_systems::{Gun, GunCooldown}, player_systems::{Player, PlayerState, PowerLevel}, util::{Materials, Speed}};
+
+pub const MAX_OPTIONS: usize = 4;
+/** Frames of player movement between each option in the trail */
+const TRAIL_SPACING: usize = 12;
+const ORBIT_RADIUS: f32 = 60.;
+const ORBIT_SPEED: f32 = 3.;
+/** How fast options catch up with their target position, per second */
+const OPTION_FOLLOW: f32 = 12.;
+
+//#region Components
+/** Satellite attached to the player as a child, fires with the player */
+pub struct PlayerOption {
+    pub index: usize,
+}
+
+pub enum OptionFormation {
+    /** Follow the player's path, Gradius style */
+    Trail,
+    /** Circle around the player at `ORBIT_RADIUS` */
+    Orbit,
+}
+
+/** Last positions of the player, newest first, only recorded while moving */
+pub struct PositionHistory(pub VecDeque<Vec3>);
+impl Default for PositionHistory {
+    fn default() -> Self {
+        Self (VecDeque::new())
+    }
+}
+//#endregion
+
+/** Amount of options granted by each power level */
+pub fn options_for_level(level: u32) -> usize {
+    match level {
+        0 | 1 => 0,
+        2 | 3 => 2,
+        _ => MAX_OPTIONS,
+    }
+}
+
+fn option_gun() -> Gun {
+    Gun {
+        damage: 0.5,
+        cooldown: GunCooldown(0., 0.25),
+        initial_speed: Speed(0., 550.),
+        ..Default::default()
+    }
+}
+
+fn option_spawn (
+    mut commands: Commands,
+    materials: Res<Materials>,
+    player_query: Query<(Entity, &PowerLevel, Option<&Children>), (With<Player>, Changed<PowerLevel>)>,
+    option_query: Query<&PlayerOption>,
+) {
+    player_query.for_each(|(player_entity, power, children)| {
+        let wanted = options_for_level(power.0);
+        let mut current = 0;
+        if let Some(children) = children {
+            for child in children.iter() {
+                if let Ok(option) = option_query.get(*child) {
+                    current += 1;
+                    if option.index >= wanted {
+                        commands.entity(*child).despawn_recursive();
+                    }
+                }
+            }
+        }
+        if current >= wanted {
+            return;
+        }
+        commands.entity(player_entity).with_children(|parent| {
+            for index in current..wanted {
+                parent
+                    .spawn_bundle(SpriteSheetBundle {
+                        texture_atlas: materials.projectile_atlas.clone(),
+                        sprite: TextureAtlasSprite {
+                            index: 3,
+                            ..Default::default()
+                        },
+                        transform: Transform {
+                            translation: Vec3::new(0., 0., -1.),
+                            scale: Vec3::new(1.5, 1.5, 1.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .insert(PlayerOption { index })
+                    .insert(option_gun());
+            }
+        });
+    });
+}
+
+fn option_record_history (
+    query: Query<(&Transform, &mut PositionHistory), (With<Player>, Changed<Transform>)>,
+) {
+    query.for_each_mut(|(transform, mut history)| {
+        let translation = transform.translation;
+        let moved = match history.0.front() {
+            Some(last) => last.distance_squared(translation) > 0.25,
+            None => true,
+        };
+        if moved {
+            history.0.push_front(translation);
+            history.0.truncate(TRAIL_SPACING * MAX_OPTIONS + 1);
+        }
+    });
+}
+
+fn option_toggle_formation (
+    keyboard_input: Res<Input<KeyCode>>,
+    query: Query<&mut OptionFormation, With<Player>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::C) {
+        return;
+    }
+    query.for_each_mut(|mut formation| {
+        *formation = match *formation {
+            OptionFormation::Trail => OptionFormation::Orbit,
+            OptionFormation::Orbit => OptionFormation::Trail,
+        };
+    });
+}
+
+fn option_movement (
+    time: Res<GameTime>,
+    player_query: Query<(&Transform, &PositionHistory, &OptionFormation, &PlayerState, &PowerLevel), With<Player>>,
+    option_query: Query<(&PlayerOption, &mut Transform), Without<Player>>,
+) {
+    let delta = time.delta().as_secs_f32();
+    let seconds = time.elapsed_seconds() as f32;
+    player_query.for_each(|(player_transform, history, formation, state, power)| {
+        let count = options_for_level(power.0).max(1);
+        option_query.for_each_mut(|(option, mut transform)| {
+            // Offset from the player in world space
+            let offset = if state.focused {
+                // Tight formation in front of the player, alternating sides
+                let side = if option.index % 2 == 0 { 1. } else { -1. };
+                let row = (option.index / 2) as f32;
+                Vec3::new(side * (24. + row * 16.), 32. - row * 8., 0.)
+            } else {
+                match formation {
+                    OptionFormation::Trail => {
+                        let position = history.0
+                            .get((option.index + 1) * TRAIL_SPACING)
+                            .or_else(|| history.0.back())
+                            .cloned()
+                            .unwrap_or(player_transform.translation);
+                        position - player_transform.translation
+                    },
+                    OptionFormation::Orbit => {
+                        let angle = seconds * ORBIT_SPEED + option.index as f32 * 2. * PI / count as f32;
+                        Vec3::new(angle.cos() * ORBIT_RADIUS, angle.sin() * ORBIT_RADIUS, 0.)
+                    },
+                }
+            };
+            // Children are positioned in the player's local space
+            let target = offset / player_transform.scale;
+            let t = (OPTION_FOLLOW * delta).min(1.);
+            transform.translation.x += (target.x - transform.translation.x) * t;
+            transform.translation.y += (target.y - transform.translation.y) * t;
+        });
+    });
+}
+
+pub struct OptionPlugin;
+impl Plugin for OptionPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .add_system(option_spawn.system())
+            .add_system(option_record_history.system())
+            .add_system(option_toggle_formation.system())
+            .add_system(option_movement.system());
+    }
+}
@@ -0,0 +1,287 @@
+use std::{collections::VecDeque, f32::consts::PI, sync::Arc};
+
+use bevy::{ecs::bundle::Bundle, math::{Quat, Vec2, Vec3}, prelude::{BuildChildren, Commands, DespawnRecursiveExt, Entity, IntoSystem, Plugin, Query, SpriteSheetBundle, Transform, With, Without}, sprite::TextureAtlasSprite};
+
+use crate::{beam_systems::BeamBlocker, collision_systems::ContactDamage, enemy_systems::{AI, AIBehavior, AIHorizontal, EnemyBundle}, gun_systems::{Gun, GunAim, GunCollection, GunCooldown}, option_systems::PositionHistory, path_systems::{AIPath, SampledPath, Spline}, util::{Health, HitBox, Materials, Speed, WinSize, facing_rotation}};
+
+//#region Components
+/** Piece of a bigger enemy, fires only while its owner's guns are active */
+pub struct Part {
+    pub owner: Entity,
+    /** The owner can't be hurt while this part is alive */
+    pub shields_core: bool,
+}
+
+/** Body of a multi-part enemy, shielded by its parts */
+pub struct Core;
+
+/** Takes no damage, kept on cores while they have shielding parts */
+pub struct Shielded;
+
+/** Head of a snake, `spacing` is how many recorded positions apart segments are */
+pub struct SnakeHead {
+    pub spacing: usize,
+    pub length: usize,
+}
+
+pub struct SnakeSegment {
+    pub index: usize,
+}
+//#endregion
+
+#[derive(Bundle)]
+pub struct PartBundle {
+    pub ai: AI,
+    pub part: Part,
+    pub health: Health,
+    pub hitbox: HitBox,
+    pub contact: ContactDamage,
+    pub weapon: GunCollection,
+
+    #[bundle]
+    pub sprite: SpriteSheetBundle
+}
+impl PartBundle {
+    pub fn new(owner: Entity, shields_core: bool, sprite: SpriteSheetBundle) -> Self {
+        Self {
+            ai: AI,
+            part: Part {
+                owner,
+                shields_core,
+            },
+            health: Health(3., 3.),
+            hitbox: HitBox {
+                rect: Vec3::new(30., 30., 1.)
+            },
+            contact: ContactDamage(20.),
+            weapon: GunCollection {
+                guns: Box::new([])
+            },
+            sprite,
+        }
+    }
+}
+
+/** Core that strafes across the top, shielded by two gun pods, with an unarmored cannon in front */
+pub fn spawn_armored (
+    commands: &mut Commands,
+    assets: &Materials,
+    win_size: &WinSize,
+) {
+    let scale = 4.;
+    let sprite = |translation: Vec3, size: f32| SpriteSheetBundle {
+        texture_atlas: assets.enemy_atlas.clone(),
+        sprite: TextureAtlasSprite {
+            index: 0,
+            ..Default::default()
+        },
+        transform: Transform {
+            translation,
+            scale: Vec3::new(size, size, 1.),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let core = commands
+        .spawn_bundle(EnemyBundle {
+            sprite: sprite(Vec3::new(win_size.half_w, win_size.h + 100., 10.), scale),
+            speed: Speed(0., 80.),
+            behavior: AIBehavior::strafing(),
+            weapon: GunCollection {
+                guns: Box::new([])
+            },
+            health: Health(15., 15.),
+            hitbox: HitBox {
+                rect: Vec3::new(60., 60., 1.)
+            },
+            ..Default::default()
+        })
+        .insert(AIHorizontal {
+            x_origin: win_size.half_w,
+            y_origin: win_size.h - 140.,
+            amplitude: win_size.half_w - 160.,
+            frequency: 0.5,
+        })
+        .insert(Core)
+        .insert(Shielded)
+        .insert(BeamBlocker)
+        .id();
+
+    commands.entity(core).with_children(|parent| {
+        // Children are positioned in the core's local space
+        for side in [-1., 1.].iter() {
+            parent.spawn_bundle(PartBundle {
+                weapon: GunCollection {
+                    guns: Box::new([
+                        Gun {
+                            cooldown: GunCooldown(1., 1.2),
+                            initial_speed: Speed(0., -300.),
+                            aim: GunAim::AtPlayer,
+                            inaccuracy: 0.2,
+                            ..Default::default()
+                        }
+                    ])
+                },
+                ..PartBundle::new(core, true, sprite(Vec3::new(side * 60. / scale, 0., 1.), 0.6))
+            });
+        }
+        parent.spawn_bundle(PartBundle {
+            health: Health(6., 6.),
+            weapon: GunCollection {
+                guns: Box::new([
+                    Gun {
+                        cooldown: GunCooldown(1.5, 2.),
+                        initial_speed: Speed(0., -250.),
+                        aim: GunAim::Facing(0.),
+                        inaccuracy: 0.6,
+                        ..Default::default()
+                    }
+                ])
+            },
+            ..PartBundle::new(core, false, sprite(Vec3::new(0., -40. / scale, 1.), 0.5))
+        });
+    });
+}
+
+/** Head following a path with `length` segments trailing behind it */
+pub fn spawn_snake (
+    commands: &mut Commands,
+    assets: &Materials,
+    win_size: &WinSize,
+    length: usize,
+    mirror: bool,
+) {
+    let (w, h) = (win_size.w, win_size.h);
+    let path = Arc::new(SampledPath::new(&Spline::Bezier(vec![
+        Vec2::new(w * 0.1, h + 60.),
+        Vec2::new(w * 0.1, h * 0.3),
+        Vec2::new(w * 0.9, h * 0.9),
+        Vec2::new(w * 0.9, h * 0.5),
+        Vec2::new(w * 0.9, h * 0.1),
+        Vec2::new(w * 0.2, h * 0.2),
+        Vec2::new(w * 0.3, -150.),
+    ])));
+    let sprite = |size: f32| SpriteSheetBundle {
+        texture_atlas: assets.enemy_atlas.clone(),
+        sprite: TextureAtlasSprite {
+            index: 0,
+            ..Default::default()
+        },
+        transform: Transform {
+            translation: Vec3::new(w * 0.1, h + 100., 10.),
+            scale: Vec3::new(size, size, 1.),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let head = commands
+        .spawn_bundle(EnemyBundle {
+            sprite: sprite(3.5),
+            behavior: AIBehavior::following_path(),
+            health: Health(8., 8.),
+            ..Default::default()
+        })
+        .insert(AIPath {
+            face_along: true,
+            mirror,
+            ..AIPath::new(path, 180.)
+        })
+        .insert(SnakeHead {
+            spacing: 8,
+            length,
+        })
+        .insert(PositionHistory::default())
+        .id();
+    for index in 0..length {
+        let mut segment = commands.spawn_bundle(PartBundle::new(head, false, sprite(2.5)));
+        segment.insert(SnakeSegment { index });
+        // Every other segment fires sideways
+        if index % 2 == 1 {
+            segment.insert(GunCollection {
+                guns: Box::new([
+                    Gun {
+                        cooldown: GunCooldown(1. + index as f32 * 0.2, 2.5),
+                        initial_speed: Speed(0., -200.),
+                        aim: GunAim::Facing(PI / 2.),
+                        ..Default::default()
+                    },
+                    Gun {
+                        cooldown: GunCooldown(1. + index as f32 * 0.2, 2.5),
+                        initial_speed: Speed(0., -200.),
+                        aim: GunAim::Facing(-PI / 2.),
+                        ..Default::default()
+                    },
+                ])
+            });
+        }
+    }
+}
+
+fn core_shield_update (
+    mut commands: Commands,
+    core_query: Query<(Entity, Option<&Shielded>), With<Core>>,
+    part_query: Query<&Part>,
+) {
+    core_query.for_each(|(entity, shielded)| {
+        let shielding = part_query.iter().any(|part| part.owner == entity && part.shields_core);
+        match (shielding, shielded.is_some()) {
+            (true, false) => { commands.entity(entity).insert(Shielded); },
+            (false, true) => { commands.entity(entity).remove::<Shielded>(); },
+            _ => {},
+        }
+    });
+}
+
+fn snake_record_history (
+    query: Query<(&Transform, &mut PositionHistory, &SnakeHead)>,
+) {
+    query.for_each_mut(|(transform, mut history, head)| {
+        history.0.push_front(transform.translation);
+        history.0.truncate(head.spacing * (head.length + 1) + 1);
+    });
+}
+
+fn snake_segment_follow (
+    mut commands: Commands,
+    head_query: Query<(&Transform, &PositionHistory, &SnakeHead)>,
+    order_query: Query<(&Part, &SnakeSegment)>,
+    segment_query: Query<(Entity, &mut Transform, &Part, &SnakeSegment), Without<SnakeHead>>,
+) {
+    // Segments close up the gaps left by destroyed ones
+    let order: Vec<(Entity, usize)> = order_query.iter()
+        .map(|(part, segment)| (part.owner, segment.index))
+        .collect();
+    segment_query.for_each_mut(|(entity, mut transform, part, segment)| {
+        let (head_transform, history, head) = match head_query.get(part.owner) {
+            Ok(head) => head,
+            Err(_) => {
+                // The body goes with the head, without drops whether it was destroyed or left
+                commands.entity(entity).despawn_recursive();
+                return;
+            },
+        };
+        let rank = order.iter()
+            .filter(|(owner, index)| *owner == part.owner && *index < segment.index)
+            .count();
+        let history: &VecDeque<Vec3> = &history.0;
+        let at = |i: usize| history.get(i).or_else(|| history.back()).cloned().unwrap_or(head_transform.translation);
+        let position = at((rank + 1) * head.spacing);
+        let ahead = at((rank + 1) * head.spacing - 1);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        let direction = (ahead - position).truncate();
+        if direction != Vec2::ZERO {
+            transform.rotation = facing_rotation(direction);
+        }
+    });
+}
+
+pub struct PartPlugin;
+impl Plugin for PartPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .add_system(core_shield_update.system())
+            .add_system(snake_record_history.system())
+            .add_system(snake_segment_follow.system());
+    }
+}
@@ -0,0 +1,241 @@
+use std::sync::Arc;
+
+use bevy::{math::Vec2, prelude::{IntoSystem, Plugin, Query, Res, Transform, With}};
+
+use crate::{enemy_systems::{AI, AIBehavior, AIMovement, AIState}, game_systems::GameTime, util::{WinSize, facing_rotation}};
+
+/** Samples taken per spline segment when measuring its length */
+const SAMPLES_PER_SEGMENT: usize = 24;
+
+pub enum Spline {
+    /** Chained cubic Béziers, `3n + 1` points: start, two handles, end, two handles, end... */
+    Bezier(Vec<Vec2>),
+    /** Passes through every point */
+    CatmullRom(Vec<Vec2>),
+}
+impl Spline {
+    fn segments(&self) -> usize {
+        match self {
+            Spline::Bezier(points) => points.len().saturating_sub(1) / 3,
+            Spline::CatmullRom(points) => points.len().saturating_sub(1),
+        }
+    }
+
+    /** Point at `t` along `segment`, `t` going from 0 to 1 */
+    fn point(&self, segment: usize, t: f32) -> Vec2 {
+        match self {
+            Spline::Bezier(points) => {
+                let p = &points[segment * 3..segment * 3 + 4];
+                let u = 1. - t;
+                p[0] * (u * u * u)
+                    + p[1] * (3. * u * u * t)
+                    + p[2] * (3. * u * t * t)
+                    + p[3] * (t * t * t)
+            },
+            Spline::CatmullRom(points) => {
+                // Ends are repeated so the curve reaches the first and last points
+                let last = points.len() - 1;
+                let p0 = points[segment.saturating_sub(1)];
+                let p1 = points[segment];
+                let p2 = points[(segment + 1).min(last)];
+                let p3 = points[(segment + 2).min(last)];
+                let t2 = t * t;
+                let t3 = t2 * t;
+                (p1 * 2.
+                    + (p2 - p0) * t
+                    + (p0 * 2. - p1 * 5. + p2 * 4. - p3) * t2
+                    + (p1 * 3. - p0 - p2 * 3. + p3) * t3) * 0.5
+            },
+        }
+    }
+}
+
+/** Spline sampled by distance travelled, so it can be followed at a constant speed */
+pub struct SampledPath {
+    points: Vec<Vec2>,
+    /** Distance along the path of each point */
+    distances: Vec<f32>,
+}
+impl SampledPath {
+    pub fn new(spline: &Spline) -> Self {
+        let mut points = Vec::new();
+        let mut distances = Vec::new();
+        for segment in 0..spline.segments() {
+            // Segments share their end point with the next one's start
+            let first = if segment == 0 { 0 } else { 1 };
+            for i in first..=SAMPLES_PER_SEGMENT {
+                let point = spline.point(segment, i as f32 / SAMPLES_PER_SEGMENT as f32);
+                let distance = match points.last() {
+                    Some(last) => distances[distances.len() - 1] + point.distance(*last),
+                    None => 0.,
+                };
+                points.push(point);
+                distances.push(distance);
+            }
+        }
+        Self { points, distances }
+    }
+
+    pub fn length(&self) -> f32 {
+        self.distances.last().cloned().unwrap_or(0.)
+    }
+
+    /** Position and direction at `distance`, carrying on in a straight line past the ends */
+    pub fn sample(&self, distance: f32) -> (Vec2, Vec2) {
+        if self.points.len() < 2 {
+            return (self.points.first().cloned().unwrap_or(Vec2::ZERO), Vec2::new(1., 0.));
+        }
+        let last = self.points.len() - 1;
+        let index = match self.distances.binary_search_by(|d| d.total_cmp(&distance)) {
+            Ok(index) => index.min(last - 1),
+            Err(index) => index.clamp(1, last) - 1,
+        };
+        let (a, b) = (self.points[index], self.points[index + 1]);
+        let (da, db) = (self.distances[index], self.distances[index + 1]);
+        let t = if db > da { (distance - da) / (db - da) } else { 0. };
+        let direction = b - a;
+        (a + direction * t, direction.normalize_or_zero())
+    }
+}
+
+//#region Components
+/** Follows a spline, paths can be shared by every member of a group */
+pub struct AIPath {
+    pub path: Arc<SampledPath>,
+    /** Units per second */
+    pub speed: f32,
+    /** Distance travelled, starts negative for members further back in a column */
+    pub distance: f32,
+    /** Rotate the sprite to face where it's heading */
+    pub face_along: bool,
+    /** Start over once at the end, for closed paths */
+    pub looping: bool,
+    /** Flip the path around the middle of the screen */
+    pub mirror: bool,
+}
+impl AIPath {
+    pub fn new(path: Arc<SampledPath>, speed: f32) -> Self {
+        Self {
+            path,
+            speed,
+            distance: 0.,
+            face_along: false,
+            looping: false,
+            mirror: false,
+        }
+    }
+    /** Member `index` of a column, `spacing` units behind the one ahead of it */
+    pub fn in_column(self, index: usize, spacing: f32) -> Self {
+        Self {
+            distance: -(index as f32) * spacing,
+            ..self
+        }
+    }
+}
+//#endregion
+
+fn enemy_path_movement (
+    time: Res<GameTime>,
+    win_size: Res<WinSize>,
+    query: Query<(&mut Transform, &mut AIPath, &mut AIState, &AIBehavior), With<AI>>,
+) {
+    let delta = time.delta().as_secs_f32();
+    query.for_each_mut(|(mut transform, mut path, mut state, behavior)| {
+        let config = behavior.config(state.movement);
+        if !matches!(config.movement, AIMovement::Path) {
+            return;
+        }
+        path.distance += path.speed * delta;
+        let length = path.path.length();
+        if path.distance > length {
+            if path.looping && length > 0. {
+                path.distance %= length;
+            } else {
+                // Path done, move on without waiting for the state to time out
+                state.enter(config.next);
+                path.distance = length;
+            }
+        }
+
+        let (mut position, mut direction) = path.path.sample(path.distance);
+        if path.mirror {
+            position.x = win_size.w - position.x;
+            direction.x = -direction.x;
+        }
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+        if path.face_along {
+            transform.rotation = facing_rotation(direction);
+        }
+    });
+}
+
+pub struct PathPlugin;
+impl Plugin for PathPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .add_system(enemy_path_movement.system());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 0.01;
+
+    /** Straight along x, with handles bunched at the start so `t` doesn't follow the distance */
+    fn straight() -> SampledPath {
+        SampledPath::new(&Spline::Bezier(vec![
+            Vec2::new(0., 0.),
+            Vec2::new(5., 0.),
+            Vec2::new(20., 0.),
+            Vec2::new(100., 0.),
+        ]))
+    }
+
+    #[test]
+    fn distances_follow_the_length() {
+        let path = straight();
+        assert!((path.length() - 100.).abs() < EPSILON);
+        for distance in [0., 5., 37.5, 80., 100.].iter() {
+            let (position, direction) = path.sample(*distance);
+            assert!((position.x - distance).abs() < EPSILON, "{} at {}", position, distance);
+            assert!(position.y.abs() < EPSILON);
+            assert!((direction - Vec2::new(1., 0.)).length() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn equal_steps_along_a_curve() {
+        // Quarter circle-ish, slow in the middle of each segment
+        let path = SampledPath::new(&Spline::Bezier(vec![
+            Vec2::new(0., 0.),
+            Vec2::new(0., 55.),
+            Vec2::new(45., 100.),
+            Vec2::new(100., 100.),
+        ]));
+        let steps = 20;
+        let step = path.length() / steps as f32;
+        let points: Vec<Vec2> = (0..=steps).map(|i| path.sample(i as f32 * step).0).collect();
+        for pair in points.windows(2) {
+            assert!((pair[0].distance(pair[1]) - step).abs() < step * 0.02);
+        }
+    }
+
+    #[test]
+    fn straight_on_past_both_ends() {
+        let path = straight();
+        let (before, direction) = path.sample(-20.);
+        assert!((before - Vec2::new(-20., 0.)).length() < EPSILON);
+        assert!((direction - Vec2::new(1., 0.)).length() < EPSILON);
+        let (after, direction) = path.sample(130.);
+        assert!((after - Vec2::new(130., 0.)).length() < EPSILON);
+        assert!((direction - Vec2::new(1., 0.)).length() < EPSILON);
+    }
+
+    #[test]
+    fn nan_distance_doesnt_panic() {
+        straight().sample(f32::NAN);
+    }
+}
@@ -0,0 +1,158 @@
+use bevy::{math::{Vec2, Vec3}, prelude::{Assets, Commands, Entity, EventReader, Handle, IntoSystem, Plugin, Query, Res, ResMut, SpriteBundle, Transform, With}, render::color::Color, sprite::{ColorMaterial, Sprite}};
+
+use crate::{bomb_systems::Bombs, game_systems::{GameState, GameTime}, health_systems::EnemyDestroyed, player_systems::{Lives, MAX_LIVES, MAX_POWER_LEVEL, Player, PowerLevel}, util::Speed};
+
+const PICKUP_RADIUS: f32 = 32.;
+const PICKUP_GRAVITY: f32 = 300.;
+const PICKUP_FALL_SPEED: f32 = -120.;
+
+//#region Components
+#[derive(Clone, Copy, PartialEq)]
+pub enum PickupKind {
+    Power,
+    Bomb,
+    Life,
+    Score,
+}
+
+pub struct Pickup {
+    pub kind: PickupKind,
+}
+//#endregion
+
+//#region Resources
+pub struct PickupMaterials {
+    pub power: Handle<ColorMaterial>,
+    pub bomb: Handle<ColorMaterial>,
+    pub life: Handle<ColorMaterial>,
+    pub score: Handle<ColorMaterial>,
+}
+
+/** Cycles through `DROP_TABLE`, one entry per enemy destroyed */
+pub struct DropTable {
+    pub kills: usize,
+}
+
+const DROP_TABLE: [Option<PickupKind>; 16] = [
+    Some(PickupKind::Score), Some(PickupKind::Power), None, Some(PickupKind::Score),
+    Some(PickupKind::Power), None, Some(PickupKind::Score), Some(PickupKind::Power),
+    None, Some(PickupKind::Bomb), Some(PickupKind::Score), Some(PickupKind::Power),
+    None, Some(PickupKind::Score), Some(PickupKind::Power), Some(PickupKind::Life),
+];
+//#endregion
+
+fn pickup_setup (
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(PickupMaterials {
+        power: materials.add(Color::RED.into()),
+        bomb: materials.add(Color::GREEN.into()),
+        life: materials.add(Color::PINK.into()),
+        score: materials.add(Color::GOLD.into()),
+    });
+    commands.insert_resource(DropTable {
+        kills: 0,
+    });
+}
+
+pub fn spawn_pickup (
+    commands: &mut Commands,
+    materials: &PickupMaterials,
+    kind: PickupKind,
+    translation: Vec3,
+) {
+    let material = match kind {
+        PickupKind::Power => materials.power.clone(),
+        PickupKind::Bomb => materials.bomb.clone(),
+        PickupKind::Life => materials.life.clone(),
+        PickupKind::Score => materials.score.clone(),
+    };
+    commands
+        .spawn_bundle(SpriteBundle {
+            material,
+            sprite: Sprite::new(Vec2::new(12., 12.)),
+            transform: Transform {
+                translation: Vec3::new(translation.x, translation.y, 5.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(Pickup { kind })
+        // Pop up a bit before falling down
+        .insert(Speed(0., 150.));
+}
+
+fn pickup_drop (
+    mut commands: Commands,
+    materials: Res<PickupMaterials>,
+    mut drop_table: ResMut<DropTable>,
+    mut destroyed: EventReader<EnemyDestroyed>,
+) {
+    for event in destroyed.iter() {
+        let kind = DROP_TABLE[drop_table.kills % DROP_TABLE.len()];
+        drop_table.kills += 1;
+        if let Some(kind) = kind {
+            spawn_pickup(&mut commands, &materials, kind, event.translation);
+        }
+    }
+}
+
+fn pickup_movement (
+    mut commands: Commands,
+    time: Res<GameTime>,
+    query: Query<(Entity, &mut Transform, &mut Speed, With<Pickup>)>,
+) {
+    let delta = time.delta().as_secs_f32();
+    query.for_each_mut(|(entity, mut transform, mut speed, _)| {
+        speed.1 = (speed.1 - PICKUP_GRAVITY * delta).max(PICKUP_FALL_SPEED);
+        // Drift sideways while falling
+        speed.0 = (transform.translation.y / 20.).sin() * 30.;
+        transform.translation.x += speed.0 * delta;
+        transform.translation.y += speed.1 * delta;
+        if transform.translation.y < -PICKUP_RADIUS {
+            commands.entity(entity).despawn();
+        }
+    });
+}
+
+fn pickup_collect (
+    mut commands: Commands,
+    mut game_state: ResMut<GameState>,
+    player_query: Query<(&Transform, &mut PowerLevel, &mut Bombs, &mut Lives), With<Player>>,
+    pickup_query: Query<(Entity, &Transform, &Pickup)>,
+) {
+    player_query.for_each_mut(|(player_transform, mut power, mut bombs, mut lives)| {
+        pickup_query.for_each(|(entity, transform, pickup)| {
+            let distance = player_transform.translation.truncate()
+                .distance(transform.translation.truncate());
+            if distance > PICKUP_RADIUS {
+                return;
+            }
+            match pickup.kind {
+                PickupKind::Power => {
+                    if power.0 < MAX_POWER_LEVEL {
+                        power.0 += 1;
+                    } else {
+                        game_state.score += 500;
+                    }
+                },
+                PickupKind::Bomb => bombs.add(1),
+                PickupKind::Life => lives.0 = (lives.0 + 1).min(MAX_LIVES),
+                PickupKind::Score => game_state.score += 100,
+            }
+            commands.entity(entity).despawn();
+        });
+    });
+}
+
+pub struct PickupPlugin;
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .add_startup_system(pickup_setup.system())
+            .add_system(pickup_drop.system())
+            .add_system(pickup_movement.system())
+            .add_system(pickup_collect.system());
+    }
+}
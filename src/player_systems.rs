@@ -1,18 +1,38 @@
-use bevy::{core::Time, input::Input, math::Vec3, prelude::{Bundle, Commands, IntoSystem, KeyCode, Plugin, Query, Res, SpriteSheetBundle, SystemStage, Transform, With}, sprite::TextureAtlasSprite};
+use bevy::{input::Input, math::{Vec2, Vec3}, prelude::{Bundle, Changed, Commands, DespawnRecursiveExt, Entity, GlobalTransform, HorizontalAlign, IntoSystem, KeyCode, ParallelSystemDescriptorCoercion, Plugin, Query, Res, ResMut, SpriteSheetBundle, SystemStage, Transform, VerticalAlign, With, Without}, sprite::TextureAtlasSprite, text::{Text, Text2dBundle, TextAlignment, TextStyle}};
 
-use crate::{gun_systems::{Gun, GunCollection, GunCooldown}, laser_systems::{FromPlayer, LaserBundle}, util::{Health, Materials, Speed, TIME_STEP, WinSize}};
+use crate::{beam_systems::BeamEmitter, bomb_systems::Bombs, charge_systems::Charge, collision_systems::ContactDamage, game_systems::{GameState, GameTime, StageStatus}, gun_systems::{Gun, GunCollection, GunCooldown}, health_systems::Invulnerable, laser_systems::{Damage, FromPlayer, LaserBundle, LaserPool, insert_gun_modifiers}, option_systems::{OptionFormation, PositionHistory, PlayerOption}, util::{Health, HitBox, Materials, Speed, TIME_STEP, WinSize}};
+
+pub const MAX_POWER_LEVEL: u32 = 4;
+pub const MAX_LIVES: u32 = 9;
+pub const FOCUS_SPEED: f32 = 0.5;
+/** Systems touching the player with commands run before it, it may despawn the player */
+pub const PLAYER_DEATH: &str = "player_death";
 
 pub struct Player;
 
+/** Extra lives left, the player is only despawned once this reaches 0 */
+pub struct Lives(pub u32);
+
+/** Selects the gun configuration, see `player_guns` */
+pub struct PowerLevel(pub u32);
+
+pub struct PlayerStatusText;
+
 pub struct PlayerState {
     movement: PlayerMoveStates,
-    state_step: f32
+    state_step: f32,
+    /** Focus mode, slower movement while the focus key is held */
+    pub focused: bool,
+    /** Movement during the last frame, in units per second */
+    pub velocity: Vec2,
 }
 impl Default for PlayerState {
     fn default() -> Self {
         Self { 
             movement: PlayerMoveStates::Idle, 
-            state_step: 0. 
+            state_step: 0.,
+            focused: false,
+            velocity: Vec2::ZERO,
         }
     }
 }
@@ -32,6 +52,15 @@ pub struct PlayerBundle {
     pub player_state: PlayerState,
     pub weapon: GunCollection,
     pub health: Health,
+    pub hitbox: HitBox,
+    pub bombs: Bombs,
+    pub lives: Lives,
+    pub power: PowerLevel,
+    pub formation: OptionFormation,
+    pub history: PositionHistory,
+    pub charge: Charge,
+    pub beam: BeamEmitter,
+    pub contact: ContactDamage,
 
     #[bundle]
     pub sprite: SpriteSheetBundle
@@ -42,20 +71,22 @@ impl Default for PlayerBundle {
             player: Player,
             player_speed: Speed(500., 200.),
             player_state: Default::default(),
-            weapon: GunCollection {
-                guns: Box::new([
-                    Gun {
-                        cooldown: GunCooldown(0., 0.33),
-                        offset: Vec3::new(20., 0., 0.),
-                        ..Default::default()
-                    }, Gun {
-                        cooldown: GunCooldown(0., 0.33),
-                        offset: Vec3::new(-20., 0., 0.),
-                        ..Default::default()
-                    }
-                ])
-            },
+            weapon: player_guns(0),
             health: Health(100., 100.),
+            hitbox: HitBox {
+                rect: Vec3::new(8., 8., 1.)
+            },
+            bombs: Default::default(),
+            lives: Lives(2),
+            power: PowerLevel(0),
+            formation: OptionFormation::Trail,
+            history: Default::default(),
+            charge: Default::default(),
+            beam: BeamEmitter {
+                offset: Vec3::new(0., 20., 0.),
+                ..Default::default()
+            },
+            contact: ContactDamage(2.),
             sprite: SpriteSheetBundle {
                 transform: Transform {
                     scale: Vec3::new(2.,2., 1.),
@@ -67,6 +98,60 @@ impl Default for PlayerBundle {
     }
 }
 
+/** Gun configuration for each power level, stronger the higher it goes */
+pub fn player_guns(level: u32) -> GunCollection {
+    let gun = |cooldown: f32, x: f32, speed: Speed| Gun {
+        cooldown: GunCooldown(0., cooldown),
+        offset: Vec3::new(x, 0., 0.),
+        initial_speed: speed,
+        ..Default::default()
+    };
+    let homing_gun = |cooldown: f32, x: f32, speed: Speed| Gun {
+        damage: 0.5,
+        homing: Some(4.),
+        lifetime: Some(2.5),
+        ..gun(cooldown, x, speed)
+    };
+    let piercing_gun = |cooldown: f32, pierce: u32, speed: Speed| Gun {
+        pierce: Some(pierce),
+        ..gun(cooldown, 0., speed)
+    };
+    let guns = match level {
+        0 => vec![
+            gun(0.33, 20., Speed(0., 500.)),
+            gun(0.33, -20., Speed(0., 500.)),
+        ],
+        1 => vec![
+            gun(0.25, 20., Speed(0., 550.)),
+            gun(0.25, -20., Speed(0., 550.)),
+        ],
+        2 => vec![
+            gun(0.25, 20., Speed(0., 550.)),
+            gun(0.25, -20., Speed(0., 550.)),
+            gun(0.2, 0., Speed(0., 600.)),
+        ],
+        3 => vec![
+            gun(0.2, 20., Speed(0., 600.)),
+            gun(0.2, -20., Speed(0., 600.)),
+            piercing_gun(0.2, 1, Speed(0., 600.)),
+            homing_gun(0.4, 25., Speed(120., 400.)),
+            homing_gun(0.4, -25., Speed(-120., 400.)),
+        ],
+        _ => vec![
+            gun(0.15, 20., Speed(0., 650.)),
+            gun(0.15, -20., Speed(0., 650.)),
+            piercing_gun(0.15, 2, Speed(0., 650.)),
+            gun(0.25, 25., Speed(120., 550.)),
+            gun(0.25, -25., Speed(-120., 550.)),
+            homing_gun(0.3, 30., Speed(240., 350.)),
+            homing_gun(0.3, -30., Speed(-240., 350.)),
+        ],
+    };
+    GunCollection {
+        guns: guns.into_boxed_slice()
+    }
+}
+
 //#region Player Setup systems
 fn player_spawn(mut commands: Commands, materials: Res<Materials>, window: Res<WinSize>) {
     // Spawn a sprite
@@ -89,18 +174,45 @@ fn player_spawn(mut commands: Commands, materials: Res<Materials>, window: Res<W
         });
 }
 
+fn player_spawn_ui(mut commands: Commands, materials: Res<Materials>, window: Res<WinSize>) {
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: materials.font.clone(),
+                    font_size: 20.,
+                    ..Default::default()
+                },
+                TextAlignment {
+                    vertical: VerticalAlign::Top,
+                    horizontal: HorizontalAlign::Left,
+                },
+            ),
+            transform: Transform {
+                translation: Vec3::new(window.w - 15., 45., 69.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(PlayerStatusText);
+}
+
 //#endregion
 //#region Player Update Systems
 fn player_movement(
     keyboard_input: Res<Input<KeyCode>>,
     mut query: Query<(With<Player>, &mut Transform, &Speed, &mut PlayerState)>,
     ws: Res<WinSize>,
-    time: Res<Time>
+    time: Res<GameTime>
 ) {
     query.for_each_mut(
         |(_, mut transform, speed, mut state)| {
             let mut p = &mut transform.translation;
 
+            state.focused = keyboard_input.pressed(KeyCode::LShift);
+            let focus = if state.focused { FOCUS_SPEED } else { 1. };
+
             let ydir: f32 = if ws.padding_bottom + 50. < p.y && keyboard_input.pressed(KeyCode::Down) {
                 state.movement = PlayerMoveStates::MoveDown;
                 -1.
@@ -110,7 +222,7 @@ fn player_movement(
             } else {
                 0.
             };
-            p.y += ydir * speed.1 * time.delta().as_secs_f32();
+            p.y += ydir * speed.1 * focus * time.delta().as_secs_f32();
 
             let xdir: f32 = if ws.padding_left + 50. < p.x && keyboard_input.pressed(KeyCode::Left) {
                 state.movement = PlayerMoveStates::MoveLeft;
@@ -122,65 +234,78 @@ fn player_movement(
                 state.movement = PlayerMoveStates::Idle;
                 0.
             };
-            p.x += xdir * speed.0 * time.delta().as_secs_f32();
+            p.x += xdir * speed.0 * focus * time.delta().as_secs_f32();
+            state.velocity = Vec2::new(xdir * speed.0, ydir * speed.1) * focus;
         }
     );
 }
 
 fn player_shoot(
     mut commands: Commands,
+    mut pool: ResMut<LaserPool>,
     keyboard_input: Res<Input<KeyCode>>,
     assets: Res<Materials>,
     mut query: Query<(&Transform, &mut Gun, With<Player>)>,
-    mut query2: Query<(&Transform, &mut GunCollection, With<Player>)>
+    mut query2: Query<(&Transform, &mut GunCollection, &PlayerState, Option<&BeamEmitter>, With<Player>)>,
+    mut option_query: Query<(&GlobalTransform, &mut Gun), (With<PlayerOption>, Without<Player>)>
 ) {
     let mut shoot_guns = 
-        |transform: &Transform, gun: &mut Gun| {
-            let x = transform.translation.x;
-            let y = transform.translation.y;
+        |translation: &Vec3, gun: &mut Gun| {
+            let x = translation.x;
+            let y = translation.y;
             let off_x = gun.offset.x;
             let off_y = gun.offset.y;
-            commands
-                .spawn_bundle(LaserBundle {
-                    sprite: SpriteSheetBundle {
-                        texture_atlas: assets.projectile_atlas.clone(),
-                        sprite: TextureAtlasSprite {
-                            index: 0,
-                            ..Default::default() 
-                        },
-                        transform: Transform {
-                            translation: Vec3::new(x + off_x, y + off_y, 0.),
-                            scale: Vec3::new(2., 2., 1.),
-                            ..Default::default()
-                        },
+            let mut laser = pool.spawn(&mut commands, LaserBundle {
+                sprite: SpriteSheetBundle {
+                    texture_atlas: assets.projectile_atlas.clone(),
+                    sprite: TextureAtlasSprite {
+                        index: 0,
+                        ..Default::default() 
+                    },
+                    transform: Transform {
+                        translation: Vec3::new(x + off_x, y + off_y, 0.),
+                        scale: Vec3::new(2., 2., 1.),
                         ..Default::default()
                     },
-                    speed: gun.initial_speed.clone(),
                     ..Default::default()
-                }).insert(FromPlayer);
+                },
+                speed: gun.initial_speed.clone(),
+                damage: Damage(gun.damage, gun.damage),
+                ..Default::default()
+            });
+            laser.insert(FromPlayer);
+            insert_gun_modifiers(&mut laser, gun);
             gun.cooldown.0 = gun.cooldown.1;
         };
 
     query.for_each_mut(|(player_transform, mut gun, _)| {
         if keyboard_input.pressed(KeyCode::X) && gun.cooldown.0 == 0. {
-            shoot_guns(player_transform, &mut gun);
+            shoot_guns(&player_transform.translation, &mut gun);
         }
     });
 
-    query2.for_each_mut(|(player_transform, mut gun_collection, _)| {
-        if keyboard_input.pressed(KeyCode::X) {
+    query2.for_each_mut(|(player_transform, mut gun_collection, state, beam, _)| {
+        // Focused players with a beam fire that instead
+        let beaming = state.focused && beam.is_some();
+        if keyboard_input.pressed(KeyCode::X) && !beaming {
             let mut guns = &mut *gun_collection.guns;
             for gun in guns {
                 if gun.cooldown.0 == 0. {
-                    shoot_guns(player_transform, gun);
+                    shoot_guns(&player_transform.translation, gun);
                 } 
             }
         }
     });
+
+    option_query.for_each_mut(|(option_transform, mut gun)| {
+        if keyboard_input.pressed(KeyCode::X) && gun.cooldown.0 == 0. {
+            shoot_guns(&option_transform.translation, &mut gun);
+        }
+    });
 }
 
 fn player_state_update (
-    time: Res<Time>,
+    time: Res<GameTime>,
     mut query: Query<(With<Player>, &mut PlayerState)>,
 ) {
     let t = time.delta().as_secs_f32() * 10.;
@@ -226,6 +351,58 @@ fn player_sprite_update (
     )
 }
 
+fn player_power_update (
+    query: Query<(&PowerLevel, &mut GunCollection), (With<Player>, Changed<PowerLevel>)>
+) {
+    query.for_each_mut(|(power, mut gun_collection)| {
+        *gun_collection = player_guns(power.0);
+    });
+}
+
+fn player_death (
+    mut commands: Commands,
+    mut game_state: ResMut<GameState>,
+    query: Query<(Entity, &mut Health, &mut Lives, &mut PowerLevel, &mut Bombs), With<Player>>
+) {
+    query.for_each_mut(|(entity, mut health, mut lives, mut power, mut bombs)| {
+        if health.0 > 0. {
+            return;
+        }
+        if lives.0 == 0 {
+            commands.entity(entity).despawn_recursive();
+            game_state.status = StageStatus::GameOver;
+            return;
+        }
+        lives.0 -= 1;
+        health.0 = health.1;
+        if power.0 > 0 {
+            power.0 -= 1;
+        }
+        bombs.0 = bombs.0.max(Bombs::default().0);
+        commands.entity(entity).insert(Invulnerable::from_seconds(3.));
+    });
+}
+
+fn player_status_ui (
+    query: Query<(&Lives, &PowerLevel), With<Player>>,
+    text_query: Query<(&mut Text, With<PlayerStatusText>)>,
+) {
+    let (lives, power) = match query.iter().next() {
+        Some((lives, power)) => (lives.0, power.0),
+        None => (0, 0),
+    };
+    let power = if power >= MAX_POWER_LEVEL {
+        "MAX".to_owned()
+    } else {
+        (power + 1).to_string()
+    };
+    text_query.for_each_mut(|(mut text, _)| {
+        text.sections.get_mut(0).unwrap().value = [
+            "Lives ".to_owned(), lives.to_string(),
+            " Power ".to_owned(), power.clone()].concat();
+    });
+}
+
 //#endregion
 
 pub struct PlayerPlugin;
@@ -234,9 +411,13 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut bevy::prelude::AppBuilder) {
         app
             .add_startup_stage("game_setup_actors",SystemStage::single(player_spawn.system()))
+            .add_startup_system_to_stage("game_setup_ui", player_spawn_ui.system())
             .add_system(player_movement.system())
             .add_system(player_shoot.system())
             .add_system(player_sprite_update.system())
-            .add_system(player_state_update.system());
+            .add_system(player_state_update.system())
+            .add_system(player_power_update.system())
+            .add_system(player_death.system().label(PLAYER_DEATH))
+            .add_system(player_status_ui.system());
         }
 }
\ No newline at end of file
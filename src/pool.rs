@@ -0,0 +1,102 @@
+use std::marker::PhantomData;
+
+use bevy::{ecs::{bundle::Bundle, component::{Component, ComponentDescriptor, StorageType}, system::EntityCommands}, prelude::{Commands, Entity, ResMut, Visible, World}};
+
+/** Entity owned by a pool, kept around hidden instead of being despawned */
+pub struct Pooled;
+
+/**
+ * Recycles entities instead of spawning and despawning them.
+ * `Parts` are the components removed on release, so the pooled entity stops matching the queries of active ones.
+ * Components every entity has, like the sprite, stay and are overwritten on reuse.
+ * `Parts` should be registered with `register_sparse`, otherwise every release and reuse copies the entity to another table.
+ */
+pub struct Pool<Parts: Bundle> {
+    free: Vec<Entity>,
+    /** Released this frame, reused once their removal went through */
+    released: Vec<Entity>,
+    /** Entities the pool created, active or not */
+    pub allocated: usize,
+    pub active: usize,
+    parts: PhantomData<Parts>,
+}
+impl<Parts: Bundle> Default for Pool<Parts> {
+    fn default() -> Self {
+        Self {
+            free: Vec::new(),
+            released: Vec::new(),
+            allocated: 0,
+            active: 0,
+            parts: PhantomData,
+        }
+    }
+}
+impl<Parts: Bundle> Pool<Parts> {
+    /** Creates `count` free entities up front, parked the same way released ones are */
+    pub fn reserve<B: Bundle>(&mut self, commands: &mut Commands, count: usize, bundle: impl Fn() -> B) {
+        for _ in 0..count {
+            let mut entity = commands.spawn_bundle(bundle());
+            entity.insert(Pooled);
+            park::<Parts>(&mut entity);
+            self.free.push(entity.id());
+            self.allocated += 1;
+        }
+    }
+
+    /** Reuses a free entity if there's one, spawns a new one otherwise */
+    pub fn spawn<'a, 'b>(&mut self, commands: &'b mut Commands<'a>, bundle: impl Bundle) -> EntityCommands<'a, 'b> {
+        self.active += 1;
+        match self.free.pop() {
+            Some(free) => {
+                let mut entity = commands.entity(free);
+                entity.insert_bundle(bundle);
+                entity
+            },
+            None => {
+                self.allocated += 1;
+                let mut entity = commands.spawn_bundle(bundle);
+                entity.insert(Pooled);
+                entity
+            },
+        }
+    }
+
+    /** Hides the entity and strips its `Parts`, releasing twice in a frame is fine */
+    pub fn release(&mut self, commands: &mut Commands, entity: Entity) {
+        if self.released.contains(&entity) {
+            return;
+        }
+        park::<Parts>(&mut commands.entity(entity));
+        self.released.push(entity);
+        self.active = self.active.saturating_sub(1);
+    }
+
+    pub fn free(&self) -> usize {
+        self.free.len()
+    }
+}
+
+/** Strips the `Parts` and hides the entity */
+fn park<Parts: Bundle>(entity: &mut EntityCommands) {
+    entity
+        .remove_bundle::<Parts>()
+        .insert(Visible {
+            is_visible: false,
+            is_transparent: true,
+        });
+}
+
+/** Makes the entities released during the frame available again, runs once their commands were applied */
+pub fn pool_recycle<Parts: Bundle> (
+    mut pool: ResMut<Pool<Parts>>,
+) {
+    let pool = &mut *pool;
+    pool.free.append(&mut pool.released);
+}
+
+/** Keeps `T` out of the tables, adding or removing it then doesn't copy the entity's other components around */
+pub fn register_sparse<T: Component>(world: &mut World) {
+    world
+        .register_component(ComponentDescriptor::new::<T>(StorageType::SparseSet))
+        .expect("sparse components must be registered before they're used");
+}
@@ -0,0 +1,246 @@
+use std::env;
+
+use bevy::{log::warn, prelude::{EventReader, EventWriter, IntoSystem, Plugin, Query, Res, With}};
+
+use crate::{game_systems::{GameMode, RunStarted}, map_systems::MapState, player_systems::{Lives, MAX_LIVES, MAX_POWER_LEVEL, Player, PowerLevel}, stage_systems::{LoadStage, STAGES}};
+
+/** Meters the start moves by on the title screen */
+const PRACTICE_STEP: f32 = 100.;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PracticeStart {
+    Distance(f32),
+    /** Index in the wave file of the stage, from 0 */
+    Wave(u32),
+}
+
+/** Setting of the practice run picked on the title screen */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PracticeField {
+    Stage,
+    Distance,
+    Wave,
+    Power,
+    Lives,
+}
+impl PracticeField {
+    pub const ALL: [PracticeField; 5] = [
+        PracticeField::Stage,
+        PracticeField::Distance,
+        PracticeField::Wave,
+        PracticeField::Power,
+        PracticeField::Lives,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PracticeField::Stage => "Stage",
+            PracticeField::Distance => "Distance",
+            PracticeField::Wave => "Wave",
+            PracticeField::Power => "Power",
+            PracticeField::Lives => "Lives",
+        }
+    }
+}
+impl Default for PracticeField {
+    fn default() -> Self {
+        PracticeField::Stage
+    }
+}
+
+/** Where a practice run starts and with what, set with `--practice` and the options after it */
+pub struct Practice {
+    /** Index in `STAGES` */
+    pub stage: usize,
+    pub start: PracticeStart,
+    pub power: u32,
+    pub lives: u32,
+}
+impl Practice {
+    /**
+     * `--stage N` from 1, `--distance M` or `--wave N` from 1, `--power N` and `--lives N`.
+     * `None` when none of them is there, wrong values are skipped with a warning.
+     */
+    pub fn from_args(args: impl Iterator<Item = String>) -> Option<Self> {
+        let mut practice = Practice::default();
+        let mut found = false;
+        let mut args = args.skip(1);
+        while let Some(arg) = args.next() {
+            let option = arg.as_str();
+            if option == "--practice" {
+                found = true;
+                continue;
+            }
+            if !matches!(option, "--stage" | "--distance" | "--wave" | "--power" | "--lives") {
+                continue;
+            }
+            found = true;
+            let value = match args.next() {
+                Some(value) => value,
+                None => {
+                    warn!("Missing value after {}", option);
+                    break;
+                }
+            };
+            let number = value.parse::<f32>().ok().filter(|number| number.is_finite());
+            let valid = match (option, number) {
+                ("--distance", Some(distance)) => {
+                    practice.start = PracticeStart::Distance(distance);
+                    true
+                }
+                ("--stage", Some(stage)) if stage >= 1. && stage as usize <= STAGES.len() => {
+                    practice.stage = stage as usize - 1;
+                    true
+                }
+                ("--wave", Some(wave)) if wave >= 1. => {
+                    practice.start = PracticeStart::Wave(wave as u32 - 1);
+                    true
+                }
+                ("--power", Some(power)) if power >= 0. => {
+                    practice.power = (power as u32).min(MAX_POWER_LEVEL);
+                    true
+                }
+                ("--lives", Some(lives)) if lives >= 0. => {
+                    practice.lives = (lives as u32).min(MAX_LIVES);
+                    true
+                }
+                _ => false,
+            };
+            if !valid {
+                warn!("Wrong value {} for {}", value, option);
+            }
+        }
+        if found { Some(practice) } else { None }
+    }
+
+    /** Meters into the stage the run starts at, the boss shows up right away past the goal */
+    pub fn distance(&self) -> f32 {
+        let goal = STAGES[self.stage].distance;
+        match self.start {
+            PracticeStart::Distance(distance) => distance.clamp(0., goal),
+            PracticeStart::Wave(index) => MapState::wave_distance(index).min(goal),
+        }
+    }
+
+    /** Moves the start by `meters`, from wherever the wave was */
+    pub fn step(&mut self, meters: f32) {
+        let goal = STAGES[self.stage].distance;
+        self.start = PracticeStart::Distance((self.distance() + meters).clamp(0., goal));
+    }
+
+    /** Changes `field` by `steps` from the title screen, within what the stage and player allow */
+    pub fn adjust(&mut self, field: PracticeField, steps: i32) {
+        let add = |value: u32, max: u32| (value as i64 + steps as i64).clamp(0, max as i64) as u32;
+        match field {
+            PracticeField::Stage => self.stage = add(self.stage as u32, STAGES.len() as u32 - 1) as usize,
+            PracticeField::Distance => self.step(steps as f32 * PRACTICE_STEP),
+            PracticeField::Wave => {
+                // From the wave the start is at, or right after it
+                let current = match self.start {
+                    PracticeStart::Wave(index) => index,
+                    PracticeStart::Distance(_) => MapState::waves_before(self.distance()),
+                };
+                let last = MapState::waves_before(STAGES[self.stage].distance).saturating_sub(1);
+                self.start = PracticeStart::Wave(add(current, last));
+            },
+            PracticeField::Power => self.power = add(self.power, MAX_POWER_LEVEL),
+            PracticeField::Lives => self.lives = add(self.lives, MAX_LIVES),
+        }
+    }
+
+    pub fn description(&self) -> String {
+        let start = match self.start {
+            PracticeStart::Distance(_) => format!("{}m", self.distance().round()),
+            PracticeStart::Wave(index) => format!("wave {}", index + 1),
+        };
+        format!("Stage {} from {}, power {}, {} lives", self.stage + 1, start, self.power, self.lives)
+    }
+}
+impl Default for Practice {
+    fn default() -> Self {
+        Self {
+            stage: 0,
+            start: PracticeStart::Distance(0.),
+            power: 0,
+            lives: 2,
+        }
+    }
+}
+
+fn practice_start (
+    mut started: EventReader<RunStarted>,
+    mode: Res<GameMode>,
+    practice: Res<Practice>,
+    mut load: EventWriter<LoadStage>,
+    query: Query<(&mut Lives, &mut PowerLevel), With<Player>>,
+) {
+    if started.iter().next().is_none() || *mode != GameMode::Practice {
+        return;
+    }
+    load.send(LoadStage { index: practice.stage, distance: practice.distance() });
+    query.for_each_mut(|(mut lives, mut power)| {
+        lives.0 = practice.lives;
+        power.0 = practice.power;
+    });
+}
+
+pub struct PracticePlugin;
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        let practice = Practice::from_args(env::args());
+        // Straight to practice on the title screen when asked for on the command line
+        if practice.is_some() {
+            app.insert_resource(GameMode::Practice);
+        }
+        app
+            .insert_resource(practice.unwrap_or_default())
+            .insert_resource(PracticeField::default())
+            .add_system(practice_start.system());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::tests::args;
+
+    use super::*;
+
+    #[test]
+    fn no_practice_without_options() {
+        assert!(Practice::from_args(args("")).is_none());
+        assert!(Practice::from_args(args("--other")).is_none());
+    }
+
+    #[test]
+    fn options_set_the_practice() {
+        let practice = Practice::from_args(args("--stage 2 --wave 3 --power 9 --lives 1")).unwrap();
+        assert_eq!(practice.stage, 1);
+        assert_eq!(practice.start, PracticeStart::Wave(2));
+        assert_eq!(practice.power, MAX_POWER_LEVEL);
+        assert_eq!(practice.lives, 1);
+        let practice = Practice::from_args(args("--lives 1e12")).unwrap();
+        assert_eq!(practice.lives, MAX_LIVES);
+        let practice = Practice::from_args(args("--practice --distance 250")).unwrap();
+        assert_eq!(practice.start, PracticeStart::Distance(250.));
+    }
+
+    #[test]
+    fn wrong_values_are_skipped() {
+        for line in ["--distance nan", "--distance inf", "--stage 0", "--stage 99", "--wave 0", "--lives -1"].iter() {
+            let practice = Practice::from_args(args(line)).unwrap();
+            let default = Practice::default();
+            assert_eq!(practice.stage, default.stage, "{}", line);
+            assert_eq!(practice.start, default.start, "{}", line);
+            assert_eq!(practice.lives, default.lives, "{}", line);
+        }
+    }
+
+    #[test]
+    fn distance_stays_in_the_stage() {
+        let goal = STAGES[0].distance;
+        let practice = Practice { start: PracticeStart::Distance(goal * 2.), ..Practice::default() };
+        assert_eq!(practice.distance(), goal);
+        let practice = Practice { start: PracticeStart::Wave(2), ..Practice::default() };
+        assert_eq!(practice.distance(), MapState::wave_distance(2));
+    }
+}
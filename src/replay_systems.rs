@@ -0,0 +1,256 @@
+use std::{env, fs, time::Duration};
+
+use bevy::{input::{Input, InputSystem}, log::warn, prelude::{CoreStage, IntoSystem, KeyCode, Local, ParallelSystemDescriptorCoercion, Plugin, Res, ResMut}};
+
+use crate::{daily_systems::Daily, difficulty_systems::Difficulty, game_systems::{CLOCK_STAGE, GAME_CLOCK, GameMode, GameTime, Screen}, util::Rng};
+
+/** Replay of the last run, overwritten by the next one */
+const REPLAY_FILE: &str = "replay.txt";
+/** Keys read while playing, in the order of their bits in a replay frame */
+const REPLAY_KEYS: [KeyCode; 9] = [
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::X,
+    KeyCode::Z,
+    KeyCode::C,
+    KeyCode::V,
+    KeyCode::LShift,
+];
+
+/** Everything to play a run again: its seed, difficulty and mode, and the keys held on every frame */
+pub struct Replay {
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub mode: GameMode,
+    /** Day of the daily challenge, which picks its modifiers */
+    pub day: Option<u64>,
+    /** How long the frame lasted and the keys held, one bit each in `REPLAY_KEYS` order */
+    pub frames: Vec<(Duration, u16)>,
+}
+impl Replay {
+    /** Header lines for the run, then one `nanoseconds keys` line per frame */
+    pub fn text(&self) -> String {
+        let mut header = format!(
+            "seed {:016X}\ndifficulty {}\nmode {}\n",
+            self.seed, self.difficulty.name(), self.mode.name(),
+        );
+        if let Some(day) = self.day {
+            header.push_str(&format!("day {}\n", day));
+        }
+        let frames: String = self.frames.iter()
+            .map(|(delta, keys)| format!("{} {:03X}\n", delta.as_nanos(), keys))
+            .collect();
+        [header, frames].concat()
+    }
+
+    /** Reads back `text`, nothing if any line is broken since the run would go another way */
+    pub fn parse(content: &str) -> Option<Self> {
+        let mut replay = Self::default();
+        for line in content.lines() {
+            let (key, value) = line.trim().split_once(' ')?;
+            match key {
+                "seed" => replay.seed = u64::from_str_radix(value, 16).ok()?,
+                "difficulty" => replay.difficulty = *Difficulty::ALL.iter().find(|difficulty| difficulty.name() == value)?,
+                "mode" => replay.mode = *GameMode::ALL.iter().find(|mode| mode.name() == value)?,
+                "day" => replay.day = Some(value.parse().ok()?),
+                nanos => replay.frames.push((
+                    Duration::from_nanos(nanos.parse().ok()?),
+                    u16::from_str_radix(value, 16).ok()?,
+                )),
+            }
+        }
+        Some(replay)
+    }
+
+    pub fn save(&self) {
+        if let Err(error) = fs::write(REPLAY_FILE, self.text()) {
+            warn!("Couldn't save the replay: {}", error);
+        }
+    }
+}
+impl Default for Replay {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            difficulty: Difficulty::default(),
+            mode: GameMode::Stages,
+            day: None,
+            frames: Vec::new(),
+        }
+    }
+}
+
+/** Replay played in place of the keyboard and the clock */
+pub struct Playback {
+    replay: Replay,
+    /** Next frame to play */
+    frame: usize,
+}
+impl Playback {
+    /** `--replay` followed by the file of a saved replay */
+    pub fn from_args(args: impl Iterator<Item = String>) -> Option<Self> {
+        let mut args = args.skip_while(|arg| arg != "--replay").skip(1);
+        let path = args.next()?;
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(error) => {
+                warn!("Couldn't read the replay {}: {}", path, error);
+                return None;
+            }
+        };
+        match Replay::parse(&content) {
+            Some(replay) => Some(Self { replay, frame: 0 }),
+            None => {
+                warn!("Broken replay {}", path);
+                None
+            }
+        }
+    }
+}
+
+/** Keys held this frame, one bit each */
+fn held_keys(keyboard_input: &Input<KeyCode>) -> u16 {
+    REPLAY_KEYS.iter()
+        .enumerate()
+        .filter(|(_, key)| keyboard_input.pressed(**key))
+        .fold(0, |keys, (bit, _)| keys | 1 << bit)
+}
+
+fn replay_record (
+    time: Res<GameTime>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut replay: ResMut<Replay>,
+) {
+    replay.frames.push((time.delta(), held_keys(&keyboard_input)));
+}
+
+/** Saves the replay once the run is over, with the seed and settings it ended with */
+fn replay_save (
+    mut saved: Local<bool>,
+    screen: Res<Screen>,
+    rng: Res<Rng>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    daily: Res<Daily>,
+    playback: Option<Res<Playback>>,
+    mut replay: ResMut<Replay>,
+) {
+    // Practice runs start mid-stage, the replay couldn't get them back there
+    if *screen != Screen::Ending || *saved || *mode == GameMode::Practice || playback.is_some() {
+        return;
+    }
+    *saved = true;
+    replay.seed = rng.seed;
+    replay.difficulty = *difficulty;
+    replay.mode = *mode;
+    replay.day = (*mode == GameMode::Daily).then(|| daily.day);
+    replay.save();
+}
+
+/** Goes through the title and results screens on its own */
+fn playback_menus (
+    screen: Res<Screen>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+) {
+    match *screen {
+        Screen::Title | Screen::Results => keyboard_input.press(KeyCode::Return),
+        _ if keyboard_input.pressed(KeyCode::Return) => keyboard_input.release(KeyCode::Return),
+        _ => (),
+    }
+}
+
+/** Holds the keys of the frame and sets its length, before the gameplay reads either */
+fn playback_frame (
+    mut playback: ResMut<Playback>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut game_time: ResMut<GameTime>,
+) {
+    let (delta, keys) = match playback.replay.frames.get(playback.frame) {
+        Some(frame) => *frame,
+        None => return,
+    };
+    playback.frame += 1;
+    for (bit, key) in REPLAY_KEYS.iter().enumerate() {
+        if keys & 1 << bit != 0 {
+            keyboard_input.press(*key);
+        } else if keyboard_input.pressed(*key) {
+            keyboard_input.release(*key);
+        }
+    }
+    game_time.next_delta = Some(delta);
+}
+
+pub struct ReplayPlugin;
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .insert_resource(Replay::default())
+            // Only while playing, like the systems reading the keys
+            .add_system(replay_record.system())
+            .add_system_to_stage(CoreStage::PostUpdate, replay_save.system());
+
+        // The run the replay was recorded on, in place of the title screen choices
+        if let Some(playback) = Playback::from_args(env::args()) {
+            let replay = &playback.replay;
+            app
+                .insert_resource(Rng::new(replay.seed))
+                .insert_resource(replay.difficulty)
+                .insert_resource(replay.mode);
+            if let Some(day) = replay.day {
+                app.insert_resource(Daily::new(day));
+            }
+            app
+                .insert_resource(playback)
+                .add_system_to_stage(CoreStage::PreUpdate, playback_menus.system().after(InputSystem))
+                .add_system_to_stage(CLOCK_STAGE, playback_frame.system().before(GAME_CLOCK));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        let mut keyboard_input = Input::<KeyCode>::default();
+        keyboard_input.press(KeyCode::Up);
+        keyboard_input.press(KeyCode::X);
+        Replay {
+            seed: 0xabc,
+            difficulty: Difficulty::Hard,
+            mode: GameMode::ScoreAttack(2),
+            day: None,
+            frames: vec![(Duration::from_nanos(16_000_000), 0), (Duration::from_nanos(16_666_667), held_keys(&keyboard_input))],
+        }
+    }
+
+    #[test]
+    fn text_has_the_run_and_every_frame() {
+        assert_eq!(
+            replay().text(),
+            "seed 0000000000000ABC\ndifficulty Hard\nmode Attack-2min\n16000000 000\n16666667 011\n",
+        );
+    }
+
+    #[test]
+    fn parse_reads_back_the_text() {
+        let daily = Replay { mode: GameMode::Daily, day: Some(19782), ..replay() };
+        for replay in [replay(), daily].iter() {
+            let parsed = Replay::parse(&replay.text()).unwrap();
+            assert_eq!(parsed.seed, replay.seed);
+            assert_eq!(parsed.difficulty, replay.difficulty);
+            assert_eq!(parsed.mode, replay.mode);
+            assert_eq!(parsed.day, replay.day);
+            assert_eq!(parsed.frames, replay.frames);
+        }
+    }
+
+    #[test]
+    fn parse_rejects_broken_lines() {
+        assert!(Replay::parse("seed 0000000000000ABC\nmode Sideways\n").is_none());
+        assert!(Replay::parse("seed 0000000000000ABC\n16000000\n").is_none());
+        assert!(Replay::parse("seed 0000000000000ABC\nsixteen 000\n").is_none());
+    }
+}
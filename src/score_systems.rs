@@ -0,0 +1,193 @@
+use std::fs;
+
+use bevy::{log::warn, prelude::{CoreStage, IntoSystem, Local, Plugin, Res, ResMut}};
+
+use crate::{attack_systems::RunTimer, daily_systems::Daily, difficulty_systems::Difficulty, game_systems::{GameMode, GameState, Screen, StageStatus}, replay_systems::Playback};
+
+const HIGH_SCORES_FILE: &str = "highscores.txt";
+/** Scores kept in each table */
+const HIGH_SCORES_KEPT: usize = 5;
+
+/** Which end of a table holds the best entries */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Ranking {
+    /** Points, most first */
+    Highest,
+    /** Clear times in tenths of a second, fastest first */
+    Lowest,
+}
+impl Ranking {
+    pub fn of(mode: GameMode) -> Self {
+        match mode {
+            GameMode::BossRush => Ranking::Lowest,
+            _ => Ranking::Highest,
+        }
+    }
+
+    fn better(&self, score: u32, other: u32) -> bool {
+        match self {
+            Ranking::Highest => score > other,
+            Ranking::Lowest => score < other,
+        }
+    }
+
+    /** Best first */
+    fn sort(&self, scores: &mut [u32]) {
+        match self {
+            Ranking::Highest => scores.sort_unstable_by(|a, b| b.cmp(a)),
+            Ranking::Lowest => scores.sort_unstable(),
+        }
+    }
+}
+
+/**
+ * Best scores, in a table for each difficulty and mode, and for each day of the daily challenge.
+ * Saved as one `table score` line each, the name of the table can hold spaces.
+ */
+#[derive(Default)]
+pub struct HighScores(pub Vec<(String, u32)>);
+impl HighScores {
+    /** Name of the table runs of `mode` on `difficulty` go in */
+    pub fn table(difficulty: Difficulty, mode: GameMode, daily: &Daily) -> String {
+        match mode {
+            // Always on normal, a new table every day
+            GameMode::Daily => format!("{} {}", mode.name(), daily.date()),
+            _ => format!("{} {}", difficulty.name(), mode.name()),
+        }
+    }
+
+    /** Missing file or broken lines are skipped, there just aren't any scores yet */
+    pub fn load() -> Self {
+        Self::parse(&fs::read_to_string(HIGH_SCORES_FILE).unwrap_or_default())
+    }
+
+    fn parse(content: &str) -> Self {
+        let scores = content
+            .lines()
+            .filter_map(|line| {
+                let (table, score) = line.trim().rsplit_once(' ')?;
+                Some((table.trim().to_owned(), score.parse().ok()?))
+            })
+            .collect();
+        Self(scores)
+    }
+
+    pub fn save(&self) {
+        let content: String = self.0.iter()
+            .map(|(table, score)| format!("{} {}\n", table, score))
+            .collect();
+        if let Err(error) = fs::write(HIGH_SCORES_FILE, content) {
+            warn!("Couldn't save high scores: {}", error);
+        }
+    }
+
+    /** Scores of `table`, best first */
+    pub fn best(&self, table: &str, ranking: Ranking) -> Vec<u32> {
+        let mut scores: Vec<u32> = self.0.iter()
+            .filter(|(other, _)| other == table)
+            .map(|(_, score)| *score)
+            .collect();
+        ranking.sort(&mut scores);
+        scores.truncate(HIGH_SCORES_KEPT);
+        scores
+    }
+
+    /** True if the score made it in the table */
+    pub fn add(&mut self, table: &str, score: u32, ranking: Ranking) -> bool {
+        let mut best = self.best(table, ranking);
+        if best.len() >= HIGH_SCORES_KEPT && !best.iter().any(|other| ranking.better(score, *other)) {
+            return false;
+        }
+        best.push(score);
+        ranking.sort(&mut best);
+        best.truncate(HIGH_SCORES_KEPT);
+        self.0.retain(|(other, _)| other != table);
+        self.0.extend(best.into_iter().map(|score| (table.to_owned(), score)));
+        true
+    }
+}
+
+/** Records the score once the run is over, whether through the last stage or out of lives */
+fn high_score_record (
+    mut recorded: Local<bool>,
+    screen: Res<Screen>,
+    game_state: Res<GameState>,
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    daily: Res<Daily>,
+    run_timer: Res<RunTimer>,
+    playback: Option<Res<Playback>>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    let over = *screen == Screen::Ending || game_state.status == StageStatus::GameOver;
+    // Nothing to record in practice, and a replay's score is in already
+    if !over || *recorded || *mode == GameMode::Practice || playback.is_some() {
+        return;
+    }
+    *recorded = true;
+    let score = match *mode {
+        // Only clears go in, with their time
+        GameMode::BossRush if game_state.status == StageStatus::Cleared => run_timer.tenths(),
+        GameMode::BossRush => return,
+        _ => game_state.score,
+    };
+    let table = HighScores::table(*difficulty, *mode, &daily);
+    if score > 0 && high_scores.add(&table, score, Ranking::of(*mode)) {
+        high_scores.save();
+    }
+}
+
+pub struct ScorePlugin;
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .insert_resource(HighScores::load())
+            // Outside of the gameplay stage to see the ending
+            .add_system_to_stage(CoreStage::PostUpdate, high_score_record.system());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keeps_spaces_in_table_names() {
+        let scores = HighScores::parse("Normal Stages 1200\nDaily 2026-10-19 800\nbroken\n");
+        assert_eq!(scores.best("Normal Stages", Ranking::Highest), vec![1200]);
+        assert_eq!(scores.best("Daily 2026-10-19", Ranking::Highest), vec![800]);
+        assert_eq!(scores.0.len(), 2);
+    }
+
+    #[test]
+    fn add_keeps_the_best_of_each_table() {
+        let mut scores = HighScores::default();
+        for score in 1..=HIGH_SCORES_KEPT as u32 {
+            assert!(scores.add("Normal Stages", score * 100, Ranking::Highest));
+        }
+        assert!(!scores.add("Normal Stages", 50, Ranking::Highest));
+        assert!(scores.add("Normal Stages", 1000, Ranking::Highest));
+        assert!(scores.add("Hard Stages", 50, Ranking::Highest));
+        assert_eq!(scores.best("Normal Stages", Ranking::Highest), vec![1000, 500, 400, 300, 200]);
+        assert_eq!(scores.best("Hard Stages", Ranking::Highest), vec![50]);
+    }
+
+    #[test]
+    fn clear_times_rank_fastest_first() {
+        let mut scores = HighScores::default();
+        for time in 1..=HIGH_SCORES_KEPT as u32 {
+            assert!(scores.add("Normal Boss-Rush", time * 600, Ranking::Lowest));
+        }
+        assert!(!scores.add("Normal Boss-Rush", 4000, Ranking::Lowest));
+        assert!(scores.add("Normal Boss-Rush", 900, Ranking::Lowest));
+        assert_eq!(scores.best("Normal Boss-Rush", Ranking::Lowest), vec![600, 900, 1200, 1800, 2400]);
+    }
+
+    #[test]
+    fn score_attack_tables_follow_the_limit() {
+        let daily = Daily::new(0);
+        assert_eq!(HighScores::table(Difficulty::Hard, GameMode::ScoreAttack(2), &daily), "Hard Attack-2min");
+        assert_eq!(HighScores::table(Difficulty::Hard, GameMode::ScoreAttack(10), &daily), "Hard Attack-10min");
+        assert_eq!(HighScores::table(Difficulty::Hard, GameMode::Daily, &daily), "Daily 1970-01-01");
+    }
+}
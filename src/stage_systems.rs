@@ -0,0 +1,291 @@
+use bevy::{audio::Audio, ecs::schedule::ShouldRun, input::Input, math::Vec3, prelude::{AssetServer, ClearColor, Commands, DespawnRecursiveExt, Entity, EventReader, EventWriter, HorizontalAlign, IntoSystem, KeyCode, Local, Parent, Plugin, Query, Res, ResMut, SystemSet, Transform, VerticalAlign, With, Without}, render::color::Color, text::{Text, Text2dBundle, TextAlignment, TextStyle}};
+
+use crate::{attack_systems::RunTimer, bomb_systems::Bombs, boss_systems::StageClearText, enemy_systems::AI, game_systems::{Distance, GameMode, GameState, GameTime, MENU_STAGE, Screen, StageStatus}, map_systems::{MapState, Wave}, player_systems::{Lives, Player}, util::{Materials, Rng, WinSize}};
+
+/** Seconds the stage clear banner stays up before the results */
+const RESULTS_DELAY: f32 = 3.;
+const LIFE_BONUS: u32 = 5000;
+const BOMB_BONUS: u32 = 1000;
+
+pub struct StageDefinition {
+    pub name: &'static str,
+    /** Meters to travel before the boss shows up */
+    pub distance: f32,
+    /** Content of the wave file, built into the binary so it doesn't depend on the working directory */
+    pub waves: &'static str,
+    pub background: Color,
+    /** There's no track in the assets yet, stages without one stay silent */
+    pub music: Option<&'static str>,
+}
+
+/** Stages in the order they're played */
+pub static STAGES: [StageDefinition; 3] = [
+    StageDefinition {
+        name: "The Kitchen",
+        distance: 1000.,
+        waves: include_str!("../assets/stages/stage_1.waves"),
+        background: Color::rgb(0., 0., 0.),
+        music: None,
+    },
+    StageDefinition {
+        name: "The Diner",
+        distance: 1500.,
+        waves: include_str!("../assets/stages/stage_2.waves"),
+        background: Color::rgb(0.12, 0.04, 0.04),
+        music: None,
+    },
+    StageDefinition {
+        name: "The Food Court",
+        distance: 2000.,
+        waves: include_str!("../assets/stages/stage_3.waves"),
+        background: Color::rgb(0.03, 0.05, 0.12),
+        music: None,
+    },
+];
+
+/** Index of the stage being played in `STAGES` */
+#[derive(Default)]
+pub struct CurrentStage(pub usize);
+
+/** Sent to play the stage at `index` in `STAGES`, starting `distance` meters in */
+#[derive(Clone, Copy)]
+pub struct LoadStage {
+    pub index: usize,
+    pub distance: f32,
+}
+
+//#region Components
+/** Everything shown on the results screen, removed when moving on */
+pub struct ResultsText;
+
+pub struct EndingText;
+//#endregion
+
+fn text_bundle(assets: &Materials, win_size: &WinSize, value: &str, size: f32, y: f32) -> Text2dBundle {
+    Text2dBundle {
+        text: Text::with_section(
+            value,
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: size,
+                ..Default::default()
+            },
+            TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            },
+        ),
+        transform: Transform {
+            translation: Vec3::new(win_size.half_w, y, 70.),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+/** Background and music of the stage */
+fn stage_look (
+    stage: &StageDefinition,
+    clear_color: &mut ClearColor,
+    audio: &Audio,
+    asset_server: &AssetServer,
+) {
+    clear_color.0 = stage.background;
+    // Bevy can't stop a track yet, stages with music should last longer than it
+    if let Some(music) = stage.music {
+        audio.play(asset_server.load(music));
+    }
+}
+
+/** Last screen of a run, with its score and seed */
+pub fn ending_spawn (
+    commands: &mut Commands,
+    assets: &Materials,
+    win_size: &WinSize,
+    title: &str,
+    subtitle: &str,
+    score: u32,
+    rng: &Rng,
+) {
+    let lines = [
+        (title.to_owned(), 56., win_size.h * 0.7),
+        (subtitle.to_owned(), 24., win_size.h * 0.7 - 60.),
+        (format!("Final score {}", score), 32., win_size.half_h - 40.),
+        (rng.seed_text(), 16., win_size.h * 0.2),
+        ("Thanks for playing, Esc to quit".to_owned(), 16., win_size.h * 0.12),
+    ];
+    for (value, size, y) in lines.iter() {
+        commands
+            .spawn_bundle(text_bundle(assets, win_size, value, *size, *y))
+            .insert(EndingText);
+    }
+}
+
+fn stage_setup (
+    mut clear_color: ResMut<ClearColor>,
+    audio: Res<Audio>,
+    asset_server: Res<AssetServer>,
+) {
+    stage_look(&STAGES[0], &mut clear_color, &audio, &asset_server);
+}
+
+/** Replaces the stage being played, starting `distance` meters in */
+fn stage_load (
+    mut commands: Commands,
+    mut events: EventReader<LoadStage>,
+    audio: Res<Audio>,
+    asset_server: Res<AssetServer>,
+    mut clear_color: ResMut<ClearColor>,
+    mut current: ResMut<CurrentStage>,
+    mut game_state: ResMut<GameState>,
+    mut map_state: ResMut<MapState>,
+    enemy_query: Query<Entity, (With<AI>, Without<Parent>)>,
+) {
+    let load = match events.iter().last() {
+        Some(load) => *load,
+        None => return,
+    };
+    let stage = &STAGES[load.index];
+    current.0 = load.index;
+    // Stragglers of the last stage would otherwise carry over
+    enemy_query.for_each(|entity| commands.entity(entity).despawn_recursive());
+    game_state.distance = Distance(load.distance, stage.distance);
+    game_state.status = StageStatus::Running;
+    game_state.scroll = 0.;
+    *map_state = MapState::new(Wave::parse(stage.waves));
+    map_state.skip_to(load.distance);
+    stage_look(stage, &mut clear_color, &audio, &asset_server);
+}
+
+/** Once the banner had time to show, adds up the bonuses and opens the results */
+fn stage_clear (
+    mut commands: Commands,
+    mut elapsed: Local<f32>,
+    time: Res<GameTime>,
+    assets: Res<Materials>,
+    win_size: Res<WinSize>,
+    mode: Res<GameMode>,
+    current: Res<CurrentStage>,
+    rng: Res<Rng>,
+    run_timer: Res<RunTimer>,
+    mut screen: ResMut<Screen>,
+    mut game_state: ResMut<GameState>,
+    mut load: EventWriter<LoadStage>,
+    player_query: Query<(&Lives, &Bombs), With<Player>>,
+    banner_query: Query<Entity, With<StageClearText>>,
+) {
+    if game_state.status != StageStatus::Cleared {
+        return;
+    }
+    *elapsed += time.delta().as_secs_f32();
+    if *elapsed < RESULTS_DELAY {
+        return;
+    }
+    *elapsed = 0.;
+    banner_query.for_each(|entity| commands.entity(entity).despawn_recursive());
+    let last = current.0 + 1 >= STAGES.len();
+
+    // Straight to the next boss, no results in between
+    if *mode == GameMode::BossRush {
+        if last {
+            *screen = Screen::Ending;
+            let subtitle = ["Every boss down in ".to_owned(), run_timer.text()].concat();
+            ending_spawn(&mut commands, &assets, &win_size, "BOSS RUSH CLEAR", &subtitle, game_state.score, &rng);
+        } else {
+            let next = current.0 + 1;
+            load.send(LoadStage { index: next, distance: STAGES[next].distance });
+        }
+        return;
+    }
+
+    let (lives, bombs) = player_query.iter().next().map_or((0, 0), |(lives, bombs)| (lives.0, bombs.0));
+    let life_bonus = lives * LIFE_BONUS;
+    let bomb_bonus = bombs * BOMB_BONUS;
+    game_state.score += life_bonus + bomb_bonus;
+    *screen = Screen::Results;
+    let lines = [
+        (format!("STAGE {} CLEAR", current.0 + 1), 48., win_size.h * 0.75),
+        (STAGES[current.0].name.to_owned(), 24., win_size.h * 0.75 - 50.),
+        (format!("Lives x{}   +{}", lives, life_bonus), 24., win_size.half_h + 40.),
+        (format!("Bombs x{}   +{}", bombs, bomb_bonus), 24., win_size.half_h),
+        (format!("Score {}", game_state.score), 32., win_size.half_h - 60.),
+        (rng.seed_text(), 16., win_size.h * 0.2),
+        (if last { "X to continue to the ending" } else { "X to continue" }.to_owned(), 16., win_size.h * 0.12),
+    ];
+    for (value, size, y) in lines.iter() {
+        commands
+            .spawn_bundle(text_bundle(&assets, &win_size, value, *size, *y))
+            .insert(ResultsText);
+    }
+}
+
+/** Runs out of lives end on the ending screen too, with the seed to try the run again */
+fn game_over (
+    mut commands: Commands,
+    assets: Res<Materials>,
+    win_size: Res<WinSize>,
+    rng: Res<Rng>,
+    game_state: Res<GameState>,
+    mut screen: ResMut<Screen>,
+) {
+    if game_state.status != StageStatus::GameOver {
+        return;
+    }
+    *screen = Screen::Ending;
+    let subtitle = format!("Out of lives after {}m", game_state.distance.0.floor());
+    ending_spawn(&mut commands, &assets, &win_size, "GAME OVER", &subtitle, game_state.score, &rng);
+}
+
+/** Loads the next stage, or shows the ending after the last one */
+fn results_continue (
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    assets: Res<Materials>,
+    win_size: Res<WinSize>,
+    rng: Res<Rng>,
+    current: Res<CurrentStage>,
+    game_state: Res<GameState>,
+    mut screen: ResMut<Screen>,
+    mut load: EventWriter<LoadStage>,
+    text_query: Query<Entity, With<ResultsText>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::X) && !keyboard_input.just_pressed(KeyCode::Return) {
+        return;
+    }
+    text_query.for_each(|entity| commands.entity(entity).despawn_recursive());
+    if current.0 + 1 >= STAGES.len() {
+        *screen = Screen::Ending;
+        ending_spawn(&mut commands, &assets, &win_size, "THE END", "Every last burger is cooked", game_state.score, &rng);
+        return;
+    }
+    load.send(LoadStage { index: current.0 + 1, distance: 0. });
+    *screen = Screen::Playing;
+}
+
+fn on_results (
+    screen: Res<Screen>,
+) -> ShouldRun {
+    match *screen {
+        Screen::Results => ShouldRun::Yes,
+        _ => ShouldRun::No,
+    }
+}
+
+pub struct StagePlugin;
+impl Plugin for StagePlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .insert_resource(CurrentStage::default())
+            .add_event::<LoadStage>()
+            .add_startup_system(stage_setup.system())
+            .add_system(stage_load.system())
+            .add_system(stage_clear.system())
+            .add_system(game_over.system())
+            .add_system_set_to_stage(
+                MENU_STAGE,
+                SystemSet::new()
+                    .with_run_criteria(on_results.system())
+                    .with_system(results_continue.system())
+            );
+    }
+}
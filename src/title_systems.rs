@@ -0,0 +1,189 @@
+use bevy::{ecs::schedule::ShouldRun, input::Input, math::Vec3, prelude::{Commands, DespawnRecursiveExt, Entity, EventWriter, HorizontalAlign, IntoSystem, KeyCode, Plugin, Query, Res, ResMut, SystemSet, Transform, VerticalAlign, With, Without}, render::color::Color, text::{Text, Text2dBundle, TextAlignment, TextStyle}};
+
+use crate::{attack_systems::clock, difficulty_systems::Difficulty, daily_systems::Daily, game_systems::{GameMode, GameState, MENU_STAGE, RunStarted, Screen}, practice_systems::{Practice, PracticeField}, score_systems::{HighScores, Ranking}, util::{Materials, WinSize}};
+
+//#region Components
+/** Everything shown on the title screen, removed once the game starts */
+pub struct TitleText;
+
+pub struct DifficultyOption(pub Difficulty);
+
+pub struct ModeText;
+
+pub struct HighScoreText;
+//#endregion
+
+fn title_spawn (
+    mut commands: Commands,
+    assets: Res<Materials>,
+    win_size: Res<WinSize>,
+) {
+    let text = |value: &str, size: f32, y: f32| Text2dBundle {
+        text: Text::with_section(
+            value,
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: size,
+                ..Default::default()
+            },
+            TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            },
+        ),
+        transform: Transform {
+            translation: Vec3::new(win_size.half_w, y, 70.),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    commands
+        .spawn_bundle(text("COOK'EM UP", 56., win_size.h * 0.75))
+        .insert(TitleText);
+    commands
+        .spawn_bundle(text("", 28., win_size.half_h + 110.))
+        .insert(ModeText)
+        .insert(TitleText);
+    for (i, difficulty) in Difficulty::ALL.iter().enumerate() {
+        commands
+            .spawn_bundle(text(difficulty.name(), 28., win_size.half_h + 40. - i as f32 * 36.))
+            .insert(DifficultyOption(*difficulty))
+            .insert(TitleText);
+    }
+    commands
+        .spawn_bundle(text("", 20., win_size.h * 0.25))
+        .insert(HighScoreText)
+        .insert(TitleText);
+    commands
+        .spawn_bundle(text("Left / Right mode, Up / Down difficulty, X to start", 16., win_size.h * 0.12))
+        .insert(TitleText);
+}
+
+fn title_select (
+    keyboard_input: Res<Input<KeyCode>>,
+    mut difficulty: ResMut<Difficulty>,
+    mut mode: ResMut<GameMode>,
+    mut practice: ResMut<Practice>,
+    mut field: ResMut<PracticeField>,
+) {
+    if *mode == GameMode::Practice {
+        let current = PracticeField::ALL.iter().position(|other| *other == *field).unwrap_or(0);
+        if keyboard_input.just_pressed(KeyCode::Q) {
+            *field = PracticeField::ALL[current.saturating_sub(1)];
+        } else if keyboard_input.just_pressed(KeyCode::E) {
+            *field = PracticeField::ALL[(current + 1).min(PracticeField::ALL.len() - 1)];
+        }
+        if keyboard_input.just_pressed(KeyCode::Z) {
+            practice.adjust(*field, -1);
+        } else if keyboard_input.just_pressed(KeyCode::C) {
+            practice.adjust(*field, 1);
+        }
+    }
+    let current = GameMode::ALL.iter().position(|other| *other == *mode).unwrap_or(0);
+    if keyboard_input.just_pressed(KeyCode::Left) {
+        *mode = GameMode::ALL[current.saturating_sub(1)];
+    } else if keyboard_input.just_pressed(KeyCode::Right) {
+        *mode = GameMode::ALL[(current + 1).min(GameMode::ALL.len() - 1)];
+    }
+    let current = Difficulty::ALL.iter().position(|other| *other == *difficulty).unwrap_or(0);
+    let last = Difficulty::ALL.len() - 1;
+    let next = if keyboard_input.just_pressed(KeyCode::Up) {
+        current.saturating_sub(1)
+    } else if keyboard_input.just_pressed(KeyCode::Down) {
+        (current + 1).min(last)
+    } else {
+        return;
+    };
+    *difficulty = Difficulty::ALL[next];
+}
+
+fn title_update_ui (
+    difficulty: Res<Difficulty>,
+    mode: Res<GameMode>,
+    daily: Res<Daily>,
+    high_scores: Res<HighScores>,
+    practice: Res<Practice>,
+    field: Res<PracticeField>,
+    option_query: Query<(&mut Text, &DifficultyOption)>,
+    score_query: Query<&mut Text, (With<HighScoreText>, Without<DifficultyOption>)>,
+    mode_query: Query<&mut Text, (With<ModeText>, Without<DifficultyOption>, Without<HighScoreText>)>,
+) {
+    mode_query.for_each_mut(|mut text| {
+        text.sections.get_mut(0).unwrap().value = match *mode {
+            GameMode::Daily => ["< ", &mode.name(), " ", &daily.date(), " >"].concat(),
+            _ => ["< ", &mode.name(), " >"].concat(),
+        };
+    });
+    option_query.for_each_mut(|(mut text, option)| {
+        let section = text.sections.get_mut(0).unwrap();
+        if option.0 == *difficulty {
+            section.value = ["> ", option.0.name(), " <"].concat();
+            section.style.color = Color::YELLOW;
+        } else {
+            section.value = option.0.name().to_owned();
+            section.style.color = Color::WHITE;
+        }
+    });
+    let ranking = Ranking::of(*mode);
+    let best = high_scores.best(&HighScores::table(*difficulty, *mode, &daily), ranking);
+    let mut value = match (best.first(), ranking) {
+        (Some(time), Ranking::Lowest) => ["Best ".to_owned(), clock(*time as f32 / 10.)].concat(),
+        (Some(score), Ranking::Highest) => ["Best ".to_owned(), score.to_string()].concat(),
+        (None, _) => "No score yet".to_owned(),
+    };
+    match *mode {
+        GameMode::Daily => value = [daily.modifier_names(), " on Normal - ".to_owned(), value].concat(),
+        // Nothing to beat in practice, shows where it starts instead
+        GameMode::Practice => value = format!("{}\n{} - Q / E to pick, Z / C to change", practice.description(), field.name()),
+        _ => (),
+    }
+    score_query.for_each_mut(|mut text| {
+        text.sections.get_mut(0).unwrap().value = value.clone();
+    });
+}
+
+fn title_start (
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mode: Res<GameMode>,
+    mut screen: ResMut<Screen>,
+    mut game_state: ResMut<GameState>,
+    mut started: EventWriter<RunStarted>,
+    query: Query<Entity, With<TitleText>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::X) && !keyboard_input.just_pressed(KeyCode::Return) {
+        return;
+    }
+    if mode.endless() {
+        // No boss to reach
+        game_state.distance.1 = f32::INFINITY;
+    }
+    started.send(RunStarted);
+    *screen = Screen::Playing;
+    query.for_each(|entity| commands.entity(entity).despawn_recursive());
+}
+
+fn on_title (
+    screen: Res<Screen>,
+) -> ShouldRun {
+    match *screen {
+        Screen::Title => ShouldRun::Yes,
+        _ => ShouldRun::No,
+    }
+}
+
+pub struct TitlePlugin;
+impl Plugin for TitlePlugin {
+    fn build(&self, app: &mut bevy::prelude::AppBuilder) {
+        app
+            .add_startup_system_to_stage("game_setup_ui", title_spawn.system())
+            .add_system_set_to_stage(
+                MENU_STAGE,
+                SystemSet::new()
+                    .with_run_criteria(on_title.system())
+                    .with_system(title_select.system())
+                    .with_system(title_update_ui.system())
+                    .with_system(title_start.system())
+            );
+    }
+}
@@ -1,4 +1,6 @@
-use bevy::{math::{Vec2, Vec3}, prelude::Handle, sprite::{ColorMaterial, TextureAtlas}, text::Font};
+use std::{f32::consts::FRAC_PI_2, time::{SystemTime, UNIX_EPOCH}};
+
+use bevy::{core::Timer, log::warn, math::{Quat, Vec2, Vec3}, prelude::Handle, sprite::{ColorMaterial, TextureAtlas}, text::Font};
 
 
 pub const TIME_STEP: f32 = 1. / 60.;
@@ -26,6 +28,81 @@ pub struct WinSize {
     pub padding_left: f32,
 }
 
+/** Small xorshift generator, deterministic for a given seed */
+pub struct Random(pub u64);
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift gets stuck on zero
+        Self (seed.max(1))
+    }
+    /** Stream `index` of `seed`, mixed so neighbouring seeds and streams don't look alike */
+    pub fn stream(seed: u64, index: u64) -> Self {
+        // Splitmix64 finalizer
+        let mut x = seed.wrapping_add(index.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Self::new(x ^ (x >> 31))
+    }
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        (x >> 32) as u32
+    }
+    /** Between 0 included and 1 excluded */
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+/**
+ * Seed of the run and the streams drawn from it, one per subsystem,
+ * so drawing more numbers in one place doesn't change what happens in another.
+ */
+pub struct Rng {
+    pub seed: u64,
+    /** Generated waves and where their enemies show up */
+    pub spawns: Random,
+    /** Inaccuracy of the enemy guns */
+    pub aim: Random,
+}
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            spawns: Random::stream(seed, 0),
+            aim: Random::stream(seed, 1),
+        }
+    }
+    /** Different every run */
+    pub fn from_clock() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(1, |elapsed| elapsed.as_nanos() as u64);
+        Self::new(nanos)
+    }
+    /** `--seed` followed by the hexadecimal seed shown on the results, to play a run again */
+    pub fn from_args(args: impl Iterator<Item = String>) -> Option<Self> {
+        let mut args = args.skip_while(|arg| arg != "--seed").skip(1);
+        let value = args.next()?;
+        match u64::from_str_radix(value.trim_start_matches("0x"), 16) {
+            Ok(seed) => Some(Self::new(seed)),
+            Err(_) => {
+                warn!("Wrong value {} for --seed", value);
+                None
+            }
+        }
+    }
+    /** How the seed is shown to the player */
+    pub fn seed_text(&self) -> String {
+        format!("Seed {:016X}", self.seed)
+    }
+}
 //#endregion
 
 //#region Components
@@ -39,6 +116,14 @@ impl Default for Speed {
 
 pub struct Health(pub f32, pub f32);
 
+/** Despawned once the timer finishes */
+pub struct Lifetime(pub Timer);
+impl Lifetime {
+    pub fn from_seconds(seconds: f32) -> Self {
+        Self (Timer::from_seconds(seconds, false))
+    }
+}
+
 pub struct HitBox {
     pub rect: Vec3,
 }
@@ -48,5 +133,86 @@ impl HitBox {
         let outrect = *origin - self.rect;
         outrect.x < point.x && point.x < rect.x && outrect.y < point.y && point.y < rect.y
     }
+    /** Same as `contains` but with the box grown by `margin` on every side */
+    pub fn overlaps (&self, origin: &Vec3, point: &Vec3, margin: f32) -> bool {
+        let rect = *origin + Vec3::new(margin, margin, 0.);
+        let outrect = *origin - self.rect - Vec3::new(margin, margin, 0.);
+        outrect.x < point.x && point.x < rect.x && outrect.y < point.y && point.y < rect.y
+    }
+    /** Whether this box at `origin` touches `other` at `other_origin` */
+    pub fn intersects (&self, origin: &Vec3, other: &HitBox, other_origin: &Vec3) -> bool {
+        let min = *origin - self.rect;
+        let other_min = *other_origin - other.rect;
+        min.x < other_origin.x && other_min.x < origin.x && min.y < other_origin.y && other_min.y < origin.y
+    }
+    /** Distance along a ray to where it enters the box grown by `margin`, if it does */
+    pub fn raycast (&self, origin: &Vec3, from: Vec2, direction: Vec2, margin: f32) -> Option<f32> {
+        let max = origin.truncate() + Vec2::new(margin, margin);
+        let min = origin.truncate() - self.rect.truncate() - Vec2::new(margin, margin);
+        let mut t_min: f32 = 0.;
+        let mut t_max = f32::INFINITY;
+        for (from, direction, min, max) in [
+            (from.x, direction.x, min.x, max.x),
+            (from.y, direction.y, min.y, max.y)
+        ].iter() {
+            if direction.abs() < f32::EPSILON {
+                if from < min || from > max {
+                    return None;
+                }
+                continue;
+            }
+            let t1 = (min - from) / direction;
+            let t2 = (max - from) / direction;
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+            if t_min > t_max {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+}
+//#endregion
+
+/** Rotation of a sprite heading along `direction`, sprites face down by default */
+pub fn facing_rotation(direction: Vec2) -> Quat {
+    Quat::from_rotation_z(facing_angle(direction))
+}
+
+/** Angle around z of `facing_rotation` */
+pub fn facing_angle(direction: Vec2) -> f32 {
+    direction.y.atan2(direction.x) + FRAC_PI_2
+}
+
+/** Where a sprite with `rotation` is heading, the other way around from `facing_rotation` */
+pub fn facing_direction(rotation: Quat) -> Vec2 {
+    (rotation * Vec3::new(0., -1., 0.)).truncate()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /** Command line of the game with `options` after its name */
+    pub fn args(options: &str) -> impl Iterator<Item = String> {
+        let line = format!("cook_em_up {}", options);
+        line.split_whitespace().map(str::to_owned).collect::<Vec<_>>().into_iter()
+    }
+
+    #[test]
+    fn seed_from_args() {
+        assert_eq!(Rng::from_args(args("--seed 00000000DEADBEEF")).map(|rng| rng.seed), Some(0xdeadbeef));
+        assert_eq!(Rng::from_args(args("--practice --seed 0x2a")).map(|rng| rng.seed), Some(0x2a));
+        assert!(Rng::from_args(args("--seed nope")).is_none());
+        assert!(Rng::from_args(args("--seed")).is_none());
+        assert!(Rng::from_args(args("")).is_none());
+    }
+
+    #[test]
+    fn seed_text_reads_back() {
+        let rng = Rng::new(0x0123_4567_89ab_cdef);
+        let text = rng.seed_text();
+        let seed = text.trim_start_matches("Seed ");
+        assert_eq!(Rng::from_args(args(&["--seed ", seed].concat())).map(|rng| rng.seed), Some(rng.seed));
+    }
 }
-//#endregion
\ No newline at end of file